@@ -0,0 +1,215 @@
+//! `#[aoch::parse(day = N)]`, `#[aoch::part1]` and `#[aoch::part2]`
+//!
+//! `parse` emits the day's unit struct and its `AoCDay` impl. Since attribute macros can't see each other, the part functions are
+//! hooked up through the hidden `Part1Fn`/`Part2Fn` traits, implemented on a module-local alias of the day struct. That limits each
+//! module to a single day, so variants of a day go in their own modules, with `name = ...` to tell their structs apart.
+
+use proc_macro2::{Span, TokenStream as PM2TokenStream};
+use quote::{format_ident, quote};
+use syn::visit_mut::{self, VisitMut};
+use syn::{Ident, ItemFn, Lifetime, LitBool, LitInt, ReturnType, Type};
+
+/// The module-local alias for the day struct, emitted by `parse` and used by the part attributes
+fn day_alias() -> Ident {
+	format_ident!("__AochDay")
+}
+
+#[derive(Default)]
+pub struct ParseArgs {
+	day: Option<LitInt>,
+	name: Option<Ident>,
+	part1: Option<LitBool>,
+	part2: Option<LitBool>,
+}
+
+impl ParseArgs {
+	pub fn parse_meta(&mut self, meta: syn::meta::ParseNestedMeta) -> syn::Result<()> {
+		if meta.path.is_ident("day") {
+			self.day = Some(meta.value()?.parse()?);
+		} else if meta.path.is_ident("name") {
+			self.name = Some(meta.value()?.parse()?);
+		} else if meta.path.is_ident("part1") {
+			self.part1 = Some(meta.value()?.parse()?);
+		} else if meta.path.is_ident("part2") {
+			self.part2 = Some(meta.value()?.parse()?);
+		} else {
+			return Err(meta.error("expected `day = N`, `name = DayNNVariant`, `part1 = false` or `part2 = false`"));
+		}
+		Ok(())
+	}
+}
+
+pub fn parse(args: ParseArgs, func: ItemFn) -> syn::Result<PM2TokenStream> {
+	let day_lit = args.day
+		.ok_or_else(|| syn::Error::new(Span::call_site(), "missing day number, ie: `#[aoch::parse(day = 7)]`"))?;
+	let day_num: u8 = day_lit.base10_parse()?;
+	if ! (1..=25).contains(&day_num) {
+		return Err(syn::Error::new_spanned(&day_lit, "day number must be within the range [1, 25]"));
+	}
+	let has_part1 = args.part1.map(|b| b.value).unwrap_or(true);
+	let has_part2 = args.part2.map(|b| b.value).unwrap_or(true);
+
+	let day_struct = args.name.unwrap_or_else(|| format_ident!("Day{:02}", day_num));
+	let alias = day_alias();
+	let fn_name = &func.sig.ident;
+
+	let data_ty = match &func.sig.output {
+		ReturnType::Type(_, ty) => input_lifetime(&func, (**ty).clone()),
+		ReturnType::Default => return Err(syn::Error::new_spanned(&func.sig, "the parse function must return the day's data")),
+	};
+
+	let call = match func.sig.inputs.len() {
+		1 => quote! { #fn_name(input) },
+		2 => quote! { #fn_name(input, params) },
+		_ => return Err(syn::Error::new_spanned(&func.sig.inputs, "expected `(input: &str)` or `(input: &str, params: &aoch::Params)`")),
+	};
+
+	let answer1 = match has_part1 {
+		true => quote! { <#day_struct as ::aoch::__private::Part1Fn>::Output },
+		false => quote! { ::aoch::Unsolved },
+	};
+	let answer2 = match has_part2 {
+		true => quote! { <#day_struct as ::aoch::__private::Part2Fn>::Output },
+		false => quote! { ::aoch::Unsolved },
+	};
+
+	let part1 = has_part1.then(|| quote! {
+		fn part1(&self, data: &mut Self::Data<'_>) -> ::aoch::Answer1<Self> {
+			<#day_struct as ::aoch::__private::Part1Fn>::part1(data)
+		}
+	});
+	let part2 = has_part2.then(|| quote! {
+		const PART2_USES_PART1: bool = <#day_struct as ::aoch::__private::Part2Fn>::USES_PART1;
+
+		fn part2(&self, data: &mut Self::Data<'_>) -> ::aoch::Answer2<Self> {
+			<#day_struct as ::aoch::__private::Part2Fn>::part2(data)
+		}
+		fn part2_with(&self, data: &mut Self::Data<'_>, part1: ::aoch::Answer1<Self>) -> ::aoch::Answer2<Self> {
+			<#day_struct as ::aoch::__private::Part2Fn>::part2_with(data, part1)
+		}
+	});
+
+	Ok(quote! {
+		#func
+
+		#[derive(Debug, Clone, Copy)]
+		pub struct #day_struct;
+
+		#[doc(hidden)]
+		#[allow(dead_code)]
+		type #alias = #day_struct;
+
+		impl ::aoch::AoCDay for #day_struct {
+			type Data<'i> = #data_ty;
+			type Answer = ::aoch::Answers<#answer1, #answer2>;
+
+			fn day(&self) -> u8 { #day_lit }
+
+			fn parse<'i>(&self, input: &'i str) -> Self::Data<'i> {
+				<Self as ::aoch::AoCDay>::parse_with(self, input, &::aoch::Params::new())
+			}
+
+			#[allow(unused_variables)]
+			fn parse_with<'i>(&self, input: &'i str, params: &::aoch::Params) -> Self::Data<'i> {
+				#call
+			}
+
+			#part1
+			#part2
+		}
+	})
+}
+
+pub fn part1(func: ItemFn) -> syn::Result<PM2TokenStream> {
+	let alias = day_alias();
+	let fn_name = &func.sig.ident;
+	let answer = answer_type(&func)?;
+	if func.sig.inputs.len() != 1 {
+		return Err(syn::Error::new_spanned(&func.sig.inputs, "expected a single argument, taking the day's data"));
+	}
+
+	Ok(quote! {
+		#func
+
+		impl ::aoch::__private::Part1Fn for #alias {
+			type Output = #answer;
+
+			fn part1(data: &mut <Self as ::aoch::AoCDay>::Data<'_>) -> Self::Output {
+				#fn_name(data)
+			}
+		}
+	})
+}
+
+pub fn part2(func: ItemFn) -> syn::Result<PM2TokenStream> {
+	let alias = day_alias();
+	let fn_name = &func.sig.ident;
+	let answer = answer_type(&func)?;
+
+	// a second argument receives part 1's answer
+	let (uses_part1, part2, part2_with) = match func.sig.inputs.len() {
+		1 => (false, quote! { #fn_name(data) }, quote! { #fn_name(data) }),
+		2 => (
+			true,
+			quote! {
+				let part1 = <Self as ::aoch::__private::Part1Fn>::part1(data);
+				#fn_name(data, part1)
+			},
+			quote! { #fn_name(data, part1) },
+		),
+		_ => return Err(syn::Error::new_spanned(&func.sig.inputs, "expected the day's data, and optionally part 1's answer")),
+	};
+
+	Ok(quote! {
+		#func
+
+		impl ::aoch::__private::Part2Fn for #alias {
+			type Output = #answer;
+			const USES_PART1: bool = #uses_part1;
+
+			fn part2(data: &mut <Self as ::aoch::AoCDay>::Data<'_>) -> Self::Output {
+				#part2
+			}
+			#[allow(unused_variables)]
+			fn part2_with(data: &mut <Self as ::aoch::AoCDay>::Data<'_>, part1: ::aoch::Answer1<Self>) -> Self::Output {
+				#part2_with
+			}
+		}
+	})
+}
+
+fn answer_type(func: &ItemFn) -> syn::Result<Type> {
+	match &func.sig.output {
+		ReturnType::Type(_, ty) => Ok((**ty).clone()),
+		ReturnType::Default => Err(syn::Error::new_spanned(&func.sig, "part functions must return their answer")),
+	}
+}
+
+/// Rewrites the parse function's return type to borrow from the input as `'i`, for use as `AoCDay::Data<'i>`
+///
+/// Replaces elided lifetimes, `'_`, and any lifetime declared on the function.
+fn input_lifetime(func: &ItemFn, mut ty: Type) -> Type {
+	struct Rewrite {
+		declared: Vec<Lifetime>,
+		input: Lifetime,
+	}
+	impl VisitMut for Rewrite {
+		fn visit_lifetime_mut(&mut self, lt: &mut Lifetime) {
+			if lt.ident == "_" || self.declared.contains(lt) {
+				*lt = self.input.clone();
+			}
+		}
+		fn visit_type_reference_mut(&mut self, r: &mut syn::TypeReference) {
+			if r.lifetime.is_none() {
+				r.lifetime = Some(self.input.clone());
+			}
+			visit_mut::visit_type_reference_mut(self, r);
+		}
+	}
+
+	let declared = func.sig.generics.lifetimes()
+		.map(|def| def.lifetime.clone())
+		.collect();
+	Rewrite { declared, input: Lifetime::new("'i", Span::call_site()) }.visit_type_mut(&mut ty);
+	ty
+}
@@ -0,0 +1,167 @@
+//! `#[derive(aoch::Parse)]`, parsing a struct from a line matching its `#[aoch(pattern = "move {count} from {from} to {to}")]`.
+//!
+//! Enums give each variant a pattern, and are parsed with the first variant to match, trying those starting with the longest literal
+//! text first.
+//! Types with a lifetime implement `aoch::parsing::FromLine`, so their `&str` fields can borrow from the line, and others `FromStr`.
+
+use proc_macro2::{Span, TokenStream as PM2TokenStream};
+use quote::{format_ident, quote, ToTokens};
+use syn::{Attribute, Data, DeriveInput, Fields, LitStr, Member, Type};
+
+use crate::scan::{is_str_ref, match_segments, parse_pattern, Segment};
+
+/// The `pattern = "..."` of an item's `#[aoch(...)]` attribute
+fn pattern_attr(attrs: &[Attribute], item: &dyn ToTokens, what: &str) -> syn::Result<LitStr> {
+	let mut pattern = None;
+	for attr in attrs.iter().filter(|attr| attr.path().is_ident("aoch")) {
+		attr.parse_nested_meta(|meta| {
+			if meta.path.is_ident("pattern") {
+				pattern = Some(meta.value()?.parse()?);
+				Ok(())
+			} else {
+				Err(meta.error("expected `pattern = \"...\"`"))
+			}
+		})?;
+	}
+	pattern.ok_or_else(|| syn::Error::new_spanned(item, format!("{} needs a pattern, ie: `#[aoch(pattern = \"move {{count}} from {{from}}\")]`", what)))
+}
+
+/// A block matching `__line` against the pattern, evaluating to `Ok(#path { fields })`. Captures are named for the field they fill,
+/// ie: `{count}` or `{0}`, though tuple fields may also be filled in order with `{}`.
+fn match_fields(pattern: &LitStr, segments: &[Segment], path: PM2TokenStream, fields: &Fields) -> syn::Result<PM2TokenStream> {
+	let members: Vec<Member> = fields.iter().enumerate()
+		.map(|(i, field)| match &field.ident {
+			Some(ident) => Member::Named(ident.clone()),
+			None => Member::Unnamed(i.into()),
+		})
+		.collect();
+	let types: Vec<&Type> = fields.iter().map(|field| &field.ty).collect();
+	let mut captured = vec![false; members.len()];
+	let mut captures = Vec::new();
+	let mut next = 0;
+	for seg in segments {
+		let index = match seg {
+			Segment::Literal(_) => continue,
+			Segment::Capture(Some(name)) => members.iter()
+				.position(|member| member.to_token_stream().to_string() == *name)
+				.ok_or_else(|| syn::Error::new_spanned(pattern, format!("there is no field named `{}`", name)))?,
+			Segment::Capture(None) if matches!(fields, Fields::Unnamed(_)) => {
+				next += 1;
+				next - 1
+			},
+			Segment::Capture(None) => return Err(syn::Error::new_spanned(pattern, "captures must name their field, ie: `{count}`")),
+		};
+		match captured.get_mut(index) {
+			None => return Err(syn::Error::new_spanned(pattern, "the pattern has more captures than there are fields")),
+			Some(true) => return Err(syn::Error::new_spanned(pattern, format!("field `{}` is captured more than once", members[index].to_token_stream()))),
+			Some(seen) => *seen = true,
+		}
+		captures.push((format_ident!("__field{}", index), types[index]));
+	}
+	if let Some(missing) = captured.iter().position(|seen| ! seen) {
+		return Err(syn::Error::new_spanned(pattern, format!("field `{}` is not captured by the pattern", members[missing].to_token_stream())));
+	}
+
+	let scanner = format_ident!("__scanner");
+	let label = syn::Lifetime::new("'__scan", Span::call_site());
+	let stmts = match_segments(segments, captures, &scanner, &label);
+	let values = (0..members.len()).map(|i| format_ident!("__field{}", i));
+	Ok(quote! {
+		#label: {
+			let mut #scanner = ::aoch::parsing::Scanner::new(__line);
+			#stmts
+			::core::result::Result::Ok(#path { #( #members: #values ),* })
+		}
+	})
+}
+
+pub fn derive_parse(input: DeriveInput) -> syn::Result<PM2TokenStream> {
+	let (body, fields): (PM2TokenStream, Vec<&Fields>) = match &input.data {
+		Data::Struct(data) => {
+			let pattern = pattern_attr(&input.attrs, &input.ident, "a struct deriving `Parse`")?;
+			let segments = parse_pattern(&pattern, true)?;
+			(match_fields(&pattern, &segments, quote!(Self), &data.fields)?, vec![&data.fields])
+		},
+		Data::Enum(data) => {
+			let mut variants = Vec::new();
+			for variant in &data.variants {
+				let ident = &variant.ident;
+				let pattern = pattern_attr(&variant.attrs, ident, "each variant")?;
+				let segments = parse_pattern(&pattern, true)?;
+				let prefix = match segments.first() {
+					Some(Segment::Literal(lit)) => lit.clone(),
+					_ => String::new(),
+				};
+				let block = match_fields(&pattern, &segments, quote!(Self::#ident), &variant.fields)?;
+				variants.push((prefix, ident, block));
+			}
+			if variants.is_empty() {
+				return Err(syn::Error::new_spanned(&input.ident, "`Parse` can't be derived for an enum without variants"));
+			}
+
+			let mut expected: Vec<String> = variants.iter().map(|(prefix, _, _)| format!("{:?}", prefix)).collect();
+			expected.dedup();
+			let msg = format!("expected one of {}", expected.join(", "));
+			// the longest prefix first, so that ie: "addx " is tried before "add ", then in the order they're declared
+			variants.sort_by_key(|(prefix, _, _)| std::cmp::Reverse(prefix.len()));
+			let (prefixes, blocks): (Vec<_>, Vec<_>) = variants.into_iter().map(|(prefix, _, block)| (prefix, block)).unzip();
+			// variants may share a prefix (ie: "turn on {}" and "turn on {} through {}"), so keep trying until one matches, and
+			// report the failure that got furthest into the line
+			let body = quote! {
+				let mut __failed: ::core::option::Option<::aoch::parsing::ParseError> = ::core::option::Option::None;
+				#( if __line.starts_with(#prefixes) {
+					match #blocks {
+						::core::result::Result::Ok(parsed) => return ::core::result::Result::Ok(parsed),
+						::core::result::Result::Err(e) => if __failed.as_ref().map_or(true, |failed| e.column > failed.column) {
+							__failed = ::core::option::Option::Some(e);
+						},
+					}
+				} )*
+				::core::result::Result::Err(__failed.unwrap_or_else(|| {
+					::aoch::parsing::ParseError::new(#msg).with_text(__line).with_column(1)
+				}))
+			};
+			(body, data.variants.iter().map(|variant| &variant.fields).collect())
+		},
+		Data::Union(_) => return Err(syn::Error::new_spanned(&input.ident, "`Parse` can't be derived for unions")),
+	};
+
+	let lifetimes: Vec<_> = input.generics.lifetimes().map(|param| param.lifetime.clone()).collect();
+	if lifetimes.len() > 1 {
+		return Err(syn::Error::new_spanned(&lifetimes[1], "`Parse` types may only borrow from the line, with a single lifetime"));
+	}
+
+	// generic fields must be parseable themselves
+	let mut generics = input.generics.clone();
+	if generics.type_params().next().is_some() {
+		let bound = match lifetimes.first() {
+			Some(lt) => quote!(::aoch::parsing::FromLine<#lt>),
+			None => quote!(for<'__line> ::aoch::parsing::FromLine<'__line>),
+		};
+		let where_clause = generics.make_where_clause();
+		for ty in fields.iter().flat_map(|fields| fields.iter()).map(|field| &field.ty).filter(|ty| ! is_str_ref(ty)) {
+			where_clause.predicates.push(syn::parse_quote!(#ty: #bound));
+		}
+	}
+
+	let name = &input.ident;
+	let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+	Ok(match lifetimes.first() {
+		Some(lt) => quote! {
+			impl #impl_generics ::aoch::parsing::FromLine<#lt> for #name #ty_generics #where_clause {
+				fn from_line(__line: &#lt str) -> ::core::result::Result<Self, ::aoch::parsing::ParseError> {
+					#body
+				}
+			}
+		},
+		None => quote! {
+			impl #impl_generics ::core::str::FromStr for #name #ty_generics #where_clause {
+				type Err = ::aoch::parsing::ParseError;
+
+				fn from_str(__line: &str) -> ::core::result::Result<Self, ::aoch::parsing::ParseError> {
+					#body
+				}
+			}
+		},
+	})
+}
@@ -0,0 +1,169 @@
+//! `#[aoch::examples(day = N)]`
+//!
+//! Placed on a function returning the day (or a tuple of its variants) to test. Emits a module of the same name, with a module per
+//! example file holding a `#[test]` for each part it has an answer for, so that each passes or fails on its own.
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use proc_macro2::{Span, TokenStream as PM2TokenStream};
+use quote::{format_ident, quote};
+use syn::{ItemFn, LitInt, LitStr};
+
+#[derive(Default)]
+pub struct ExamplesArgs {
+	day: Option<LitInt>,
+	dir: Option<LitStr>,
+}
+
+impl ExamplesArgs {
+	pub fn parse_meta(&mut self, meta: syn::meta::ParseNestedMeta) -> syn::Result<()> {
+		if meta.path.is_ident("day") {
+			self.day = Some(meta.value()?.parse()?);
+		} else if meta.path.is_ident("dir") {
+			self.dir = Some(meta.value()?.parse()?);
+		} else {
+			return Err(meta.error("expected `day = N` or `dir = \"../examples\"`"));
+		}
+		Ok(())
+	}
+}
+
+pub fn examples(args: ExamplesArgs, func: ItemFn) -> syn::Result<PM2TokenStream> {
+	let day_lit = args.day
+		.ok_or_else(|| syn::Error::new(Span::call_site(), "missing day number, ie: `#[aoch::examples(day = 7)]`"))?;
+	let day: u8 = day_lit.base10_parse()?;
+	if ! (1..=25).contains(&day) {
+		return Err(syn::Error::new_spanned(&day_lit, "day number must be within the range [1, 25]"));
+	}
+	if ! func.sig.inputs.is_empty() {
+		return Err(syn::Error::new_spanned(&func.sig.inputs, "expected a function without arguments, returning the day to test"));
+	}
+
+	let mani_dir = std::env::var("CARGO_MANIFEST_DIR")
+		.expect("CARGO_MANIFEST_DIR environment variable not defined");
+	let dir = args.dir.as_ref().map(LitStr::value).unwrap_or_else(|| "../examples".to_string());
+	let dir = Path::new(&mani_dir).join(dir);
+
+	let files = example_files(&dir, day)
+		.map_err(|e| syn::Error::new(Span::call_site(), format!("unable to read examples from {:?}: {}", dir, e)))?;
+	if files.is_empty() {
+		return Err(syn::Error::new_spanned(&day_lit, format!("no examples found for day {} in {:?}", day, dir)));
+	}
+
+	let fn_name = &func.sig.ident;
+	let mut idents: Vec<(String, &String)> = Vec::new();
+	let mut tests = Vec::new();
+	for (name, path) in &files {
+		let ident = format!("example_{}", sanitize(name));
+		if let Some((_, other)) = idents.iter().find(|(i, _)| *i == ident) {
+			let msg = format!("examples {:?} and {:?} would both be tested as `{}`, rename one of them", other, name, ident);
+			return Err(syn::Error::new_spanned(&day_lit, msg));
+		}
+		idents.push((ident.clone(), name));
+
+		let text = std::fs::read_to_string(path)
+			.map_err(|e| syn::Error::new(Span::call_site(), format!("unable to read example {:?}: {}", path, e)))?;
+		let answered = answered_parts(&text);
+		let path = path.to_string_lossy();
+		let parts = answered.iter().map(|part| {
+			let test_name = format_ident!("{}", part.to_lowercase());
+			let part = format_ident!("{}", part);
+			quote! {
+				#[test]
+				fn #test_name() {
+					::aoch::testing::test_example(super::super::#fn_name(), ::aoch::#part, #name, include_str!(#path));
+				}
+			}
+		});
+		// libtest shows ignored tests, so an example that tests nothing isn't mistaken for a passing one
+		let unanswered = answered.is_empty().then(|| quote! {
+			#[test]
+			#[ignore = "no answers in the example's header"]
+			fn no_answers() {
+				let _ = include_str!(#path);
+			}
+		});
+
+		let module = format_ident!("{}", ident);
+		tests.push(quote! {
+			mod #module {
+				#( #parts )*
+				#unanswered
+			}
+		});
+	}
+
+	Ok(quote! {
+		#[allow(dead_code)]
+		#func
+
+		#[cfg(test)]
+		mod #fn_name {
+			#( #tests )*
+		}
+	})
+}
+
+/// The day's example files, as `(name, path)`, sorted by name
+fn example_files(dir: &Path, day: u8) -> std::io::Result<Vec<(String, PathBuf)>> {
+	let mut files = Vec::new();
+	for ent in dir.read_dir()? {
+		let path = ent?.path();
+		if path.extension() != Some(OsStr::new("txt")) {
+			continue;
+		}
+		if let Some(name) = path.file_stem().and_then(OsStr::to_str) {
+			let num = name.split_once('-').map_or(name, |(num, _)| num);
+			if num.parse::<u8>() == Ok(day) {
+				files.push((name.to_string(), path.clone()));
+			}
+		}
+	}
+	files.sort();
+	Ok(files)
+}
+
+/// The parts an example has an answer for in its header (ie: `part1: 6`), as checked by `aoch::testing::Example::parse`
+fn answered_parts(text: &str) -> Vec<&'static str> {
+	const KEYS: [&str; 3] = ["part1", "part2", "params"];
+	let key = |line: &str| line.split_once(':').map(|(key, _)| key.trim().to_string());
+
+	let first = text.lines().next().unwrap_or("");
+	if ! key(first).is_some_and(|key| KEYS.contains(&key.as_str())) {
+		return Vec::new();
+	}
+	let header: Vec<String> = text.lines()
+		.take_while(|line| line.trim() != "---")
+		.filter_map(key)
+		.collect();
+	[("part1", "Part1"), ("part2", "Part2")].iter()
+		.filter(|(key, _)| header.iter().any(|k| k == key))
+		.map(|&(_, part)| part)
+		.collect()
+}
+
+/// Makes a file name usable in a test function's name, ie: `07-larger` to `07_larger`
+fn sanitize(name: &str) -> String {
+	name.chars()
+		.map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parts_with_answers() {
+		assert_eq!(answered_parts("part1: 6\npart2: 12\n---\n1\n2"), ["Part1", "Part2"]);
+		assert_eq!(answered_parts("params: size=7\npart2: x\n---\npart1: 3"), ["Part2"]);
+		assert_eq!(answered_parts("1\n2\npart1: 6"), Vec::<&str>::new());
+	}
+
+	#[test]
+	fn sanitized_names() {
+		assert_eq!(sanitize("07-Larger.v2"), "07_larger_v2");
+		assert_eq!(sanitize("07-1-a"), sanitize("07_1_a"));
+	}
+}
@@ -2,13 +2,20 @@
 use std::ffi::OsStr;
 use std::path::Path;
 use std::io;
-use syn::{parse_macro_input, LitStr};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{bracketed, parse_macro_input, DeriveInput, Ident, ItemFn, LitStr, Token};
 use quote::{quote, format_ident};
 use proc_macro2::TokenStream as PM2TokenStream;
 
 extern crate proc_macro;
 use proc_macro::TokenStream as PMTokenStream;
 
+mod day_fns;
+mod derive_parse;
+mod examples;
+mod scan;
+
 #[proc_macro]
 pub fn aoc_inputs(name: PMTokenStream) -> PMTokenStream {
 	// load all files in "inputs/XX.txt"
@@ -69,14 +76,56 @@ fn get_directory_contents(input_dir: &Path) -> io::Result<[Option<String>; 25]>
 	Ok(found)
 }
 
+/// `load_days!("../input", day07 = [Day07Naive, Day07Fast])`
+///
+/// The input directory is optional. Days default to the single `DayXX` struct in their module, unless variants are listed for them.
+struct LoadDays {
+	input_dir: Option<LitStr>,
+	variants: Vec<(Ident, Vec<Ident>)>,
+}
+
+impl Parse for LoadDays {
+	fn parse(input: ParseStream) -> syn::Result<Self> {
+		let input_dir: Option<LitStr> = if input.peek(LitStr) { Some(input.parse()?) } else { None };
+
+		let mut variants = Vec::new();
+		while ! input.is_empty() {
+			if input_dir.is_some() || ! variants.is_empty() {
+				input.parse::<Token![,]>()?;
+				if input.is_empty() {
+					break;
+				}
+			}
+
+			let module: Ident = input.parse()?;
+			input.parse::<Token![=]>()?;
+			let content;
+			bracketed!(content in input);
+			let days = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+			variants.push((module, days.into_iter().collect()));
+		}
+
+		Ok(LoadDays { input_dir, variants })
+	}
+}
+
 #[proc_macro]
 pub fn load_days(name: PMTokenStream) -> PMTokenStream {
-	let input: Option<LitStr> = parse_macro_input!(name as Option<LitStr>);
-	let input_dir = input.map(|s| s.value()).unwrap_or_else(|| "../input".to_string());
+	let LoadDays { input_dir, variants } = parse_macro_input!(name as LoadDays);
+	let input_dir = input_dir.map(|s| s.value()).unwrap_or_else(|| "../input".to_string());
+
+	let max: usize = 25;
+	let day_mod_names: Vec<String> = (1..=max).map(|i| format!("day{:02}", i)).collect();
+	for (module, _) in &variants {
+		if ! day_mod_names.contains(&module.to_string()) {
+			return syn::Error::new_spanned(module, "expected a day module, within `day01` to `day25`")
+				.into_compile_error()
+				.into();
+		}
+	}
 
 	let mut day_mods = PM2TokenStream::new();
 	let mut runners = PM2TokenStream::new();
-	let max: usize = 25;
 	for i in  1..=max {
 		let n = format!("{:02}", i);
 		let smolday = format_ident!("day{}", n);
@@ -84,8 +133,13 @@ pub fn load_days(name: PMTokenStream) -> PMTokenStream {
 		day_mods.extend(quote! {
 			pub mod #smolday;
 		});
+
+		let days = match variants.iter().find(|(module, _)| *module == smolday) {
+			Some((_, days)) => days.clone(),
+			None => vec![bigday],
+		};
 		runners.extend(quote! {
-			(include_str!(concat!(#input_dir, "/", #n,".txt")), &|p, i, q| ::aoch::run_day_with_input(crate::days::#smolday::#bigday, p, q, i)),
+			(include_str!(concat!(#input_dir, "/", #n,".txt")), &[ #( &crate::days::#smolday::#days ),* ]),
 		});
 	}
 
@@ -93,8 +147,103 @@ pub fn load_days(name: PMTokenStream) -> PMTokenStream {
 		pub mod days {
 			#day_mods
 		}
-		pub const RUNNERS: [(&'static str, &dyn Fn(::std::option::Option<::aoch::DayPart>, bool, &str) -> ()); #max] = [
+		pub const RUNNERS: [(&'static str, &'static [&'static dyn ::aoch::DynDay]); #max] = [
 			#runners
 		];
 	}.into()
 }
+
+/// Defines a day from its parse function, ie: `#[aoch::parse(day = 7)]`
+///
+/// Emits the `DayXX` unit struct and its `AoCDay` impl, with the data type taken from the function's return type.
+/// Takes the raw input, and optionally the puzzle's `&aoch::Params`.
+///
+/// Parts are defined with `#[aoch::part1]` and `#[aoch::part2]`, in the same module. Parts without a function yet (or day 25's
+/// part 2) must be marked as such, ie: `#[aoch::parse(day = 25, part2 = false)]`.
+///
+/// Only one day can be defined per module, as the parts find it through the module. Variants of a day go in modules of their own,
+/// named with `name`, ie: `#[aoch::parse(day = 7, name = Day07Fast)]` within `day07::fast`, and re-exported for `load_days!`.
+#[proc_macro_attribute]
+pub fn parse(args: PMTokenStream, item: PMTokenStream) -> PMTokenStream {
+	let mut parse_args = day_fns::ParseArgs::default();
+	let arg_parser = syn::meta::parser(|meta| parse_args.parse_meta(meta));
+	parse_macro_input!(args with arg_parser);
+	let func = parse_macro_input!(item as ItemFn);
+
+	day_fns::parse(parse_args, func)
+		.unwrap_or_else(syn::Error::into_compile_error)
+		.into()
+}
+
+/// Defines a day's part 1, taking the data returned by the `#[aoch::parse]` function. The answer type is the function's return type.
+#[proc_macro_attribute]
+pub fn part1(_args: PMTokenStream, item: PMTokenStream) -> PMTokenStream {
+	let func = parse_macro_input!(item as ItemFn);
+	day_fns::part1(func)
+		.unwrap_or_else(syn::Error::into_compile_error)
+		.into()
+}
+
+/// Defines a day's part 2, taking the data returned by the `#[aoch::parse]` function. The answer type is the function's return type.
+///
+/// If the function takes a second argument, it is given part 1's answer.
+#[proc_macro_attribute]
+pub fn part2(_args: PMTokenStream, item: PMTokenStream) -> PMTokenStream {
+	let func = parse_macro_input!(item as ItemFn);
+	day_fns::part2(func)
+		.unwrap_or_else(syn::Error::into_compile_error)
+		.into()
+}
+
+/// Generates a `#[test]` for each part of each of a day's example files, ie: `#[aoch::examples(day = 7)]`
+///
+/// Placed on a function returning the day to test (or a tuple of its variants), and emits a module of the same name holding the tests,
+/// ie: `examples::example_07_larger::part1`. Only parts with an answer in the example's header are tested, and an example without
+/// any is reported as an ignored test. Files whose names only differ in punctuation (ie: `07-a-b` and `07-a_b`) are an error.
+/// Examples are read from `../examples` by default, or `dir = "..."`, relative to the day crate. Adding a new example file only
+/// takes effect once the crate is rebuilt.
+#[proc_macro_attribute]
+pub fn examples(args: PMTokenStream, item: PMTokenStream) -> PMTokenStream {
+	let mut examples_args = examples::ExamplesArgs::default();
+	let arg_parser = syn::meta::parser(|meta| examples_args.parse_meta(meta));
+	parse_macro_input!(args with arg_parser);
+	let func = parse_macro_input!(item as ItemFn);
+
+	examples::examples(examples_args, func)
+		.unwrap_or_else(syn::Error::into_compile_error)
+		.into()
+}
+
+/// Matches a line against a pattern of literal text and `{}` captures, ie: `aoch::scan!("{}-{} {}: {}", line => usize, usize, char, &str)`
+///
+/// Evaluates to a `Result` of the captures as a tuple (or the value itself, for a single capture), or a `ParseError` saying where
+/// the match failed. Each capture runs up to the next literal text in the pattern, and is parsed with `FromStr` (or `FromLine`), except for `&str`
+/// captures which borrow from the line. `{{` and `}}` match literal braces.
+///
+/// A capture ends at the *first* occurrence of the literal after it, without backtracking, so `"{}-{}"` fails to match `"-3-4"`
+/// (the first capture is empty) rather than trying the next `-`. Use `&str` captures and split them by hand for such lines.
+///
+/// The generated code refers to `::aoch::parsing`, so the macro can only be used from crates depending on `aoch` under that name.
+#[proc_macro]
+pub fn scan(input: PMTokenStream) -> PMTokenStream {
+	let args = parse_macro_input!(input as scan::ScanInput);
+	scan::scan(args)
+		.unwrap_or_else(syn::Error::into_compile_error)
+		.into()
+}
+
+/// Parses a type from a line matching a pattern, ie: `#[derive(aoch::Parse)] #[aoch(pattern = "move {count} from {from} to {to}")]`
+///
+/// Captures are named for the field they fill, and parsed as with `aoch::scan!`. Each variant of an enum has its own pattern, and
+/// the first variant to match the whole line is parsed. Variants are tried starting with those whose pattern starts with the longest
+/// literal text, then in the order they're declared, so variants may share a prefix (ie: `"turn on {}"` and `"turn on {} through {}"`)
+/// or start with a capture (ie: `"{} -> {}"` and `"{}"`, declaring the more specific first). Implements `FromStr`, or
+/// `aoch::parsing::FromLine` for types with a lifetime, whose `&str` fields borrow from the line. Either can be given to
+/// `aoch::parsing::from_lines`.
+#[proc_macro_derive(Parse, attributes(aoch))]
+pub fn derive_parse(input: PMTokenStream) -> PMTokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	derive_parse::derive_parse(input)
+		.unwrap_or_else(syn::Error::into_compile_error)
+		.into()
+}
@@ -0,0 +1,198 @@
+//! `aoch::scan!("{}-{} {}: {}", line => usize, usize, char, &str)`
+//!
+//! Matches a line against a pattern of literal text and `{}` captures, parsing each capture as its type. The pattern is checked
+//! at compile time, and the matching is done by `aoch::parsing::Scanner`.
+
+use proc_macro2::{Span, TokenStream as PM2TokenStream};
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Expr, LitStr, Token, Type};
+
+pub struct ScanInput {
+	pattern: LitStr,
+	input: Expr,
+	types: Punctuated<Type, Token![,]>,
+}
+
+impl Parse for ScanInput {
+	fn parse(input: ParseStream) -> syn::Result<Self> {
+		let pattern = input.parse()?;
+		input.parse::<Token![,]>()?;
+		let line = input.parse()?;
+		input.parse::<Token![=>]>()?;
+		let types = Punctuated::parse_terminated(input)?;
+		Ok(ScanInput { pattern, input: line, types })
+	}
+}
+
+/// A piece of a pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+	Literal(String),
+	/// A `{}` capture, or `{name}` if names are allowed
+	Capture(Option<String>),
+}
+
+/// Splits a pattern into literal text and captures. `{{` and `}}` are literal braces.
+///
+/// Captures must be separated by literal text, as there would be no way to tell where one ends and the next starts.
+pub fn parse_pattern(pattern: &LitStr, named: bool) -> syn::Result<Vec<Segment>> {
+	let text = pattern.value();
+	let mut segments = Vec::new();
+	let mut literal = String::new();
+	let mut chars = text.chars().peekable();
+	while let Some(c) = chars.next() {
+		match c {
+			'{' if chars.peek() == Some(&'{') => {
+				chars.next();
+				literal.push('{');
+			},
+			'}' if chars.peek() == Some(&'}') => {
+				chars.next();
+				literal.push('}');
+			},
+			'{' => {
+				let mut name = String::new();
+				loop {
+					match chars.next() {
+						Some('}') => break,
+						Some(c) if c.is_alphanumeric() || c == '_' => name.push(c),
+						_ if named => return Err(syn::Error::new_spanned(pattern, "expected a `{name}` capture, or `{{` for a literal brace")),
+						_ => return Err(syn::Error::new_spanned(pattern, "expected a `{}` capture, or `{{` for a literal brace")),
+					}
+				}
+				if ! named && ! name.is_empty() {
+					return Err(syn::Error::new_spanned(pattern, format!("named captures aren't supported here, use `{{}}` instead of `{{{}}}`", name)));
+				}
+				if literal.is_empty() && matches!(segments.last(), Some(Segment::Capture(_))) {
+					return Err(syn::Error::new_spanned(pattern, "captures must be separated by literal text, as there is no way to tell where one ends"));
+				}
+				if ! literal.is_empty() {
+					segments.push(Segment::Literal(std::mem::take(&mut literal)));
+				}
+				segments.push(Segment::Capture((! name.is_empty()).then_some(name)));
+			},
+			'}' => return Err(syn::Error::new_spanned(pattern, "unmatched `}`, use `}}` for a literal brace")),
+			c => literal.push(c),
+		}
+	}
+	if ! literal.is_empty() {
+		segments.push(Segment::Literal(literal));
+	}
+	Ok(segments)
+}
+
+/// Whether a capture's type is a `&str`, which borrows from the input rather than being parsed
+pub fn is_str_ref(ty: &Type) -> bool {
+	match ty {
+		Type::Reference(r) => r.mutability.is_none() && matches!(&*r.elem, Type::Path(p) if p.qself.is_none() && p.path.is_ident("str")),
+		Type::Group(g) => is_str_ref(&g.elem),
+		_ => false,
+	}
+}
+
+/// Statements matching `segments` with the scanner named `scanner`, binding each capture to its ident. A failed match breaks out
+/// of the `label` block with the error.
+pub fn match_segments<'a>(
+	segments: &[Segment],
+	captures: impl IntoIterator<Item = (syn::Ident, &'a Type)>,
+	scanner: &syn::Ident,
+	label: &syn::Lifetime,
+) -> PM2TokenStream {
+	let mut captures = captures.into_iter();
+	let mut stmts = Vec::new();
+	for (i, seg) in segments.iter().enumerate() {
+		match seg {
+			Segment::Literal(lit) => stmts.push(quote! {
+				if let ::core::result::Result::Err(e) = #scanner.literal(#lit) {
+					break #label ::core::result::Result::Err(e);
+				}
+			}),
+			Segment::Capture(_) => {
+				let (ident, ty) = captures.next().expect("one type per capture");
+				let until = match segments.get(i + 1) {
+					Some(Segment::Literal(next)) => quote! { ::core::option::Option::Some(#next) },
+					_ => quote! { ::core::option::Option::None },
+				};
+				let take = if is_str_ref(ty) {
+					quote! { #scanner.capture(#until) }
+				} else {
+					let name = quote!(#ty).to_string();
+					quote! { #scanner.parse::<#ty>(#until, #name) }
+				};
+				stmts.push(quote! {
+					let #ident: #ty = match #take {
+						::core::result::Result::Ok(v) => v,
+						::core::result::Result::Err(e) => break #label ::core::result::Result::Err(e),
+					};
+				});
+			},
+		}
+	}
+	stmts.push(quote! {
+		if let ::core::result::Result::Err(e) = #scanner.end() {
+			break #label ::core::result::Result::Err(e);
+		}
+	});
+	quote! { #( #stmts )* }
+}
+
+pub fn scan(args: ScanInput) -> syn::Result<PM2TokenStream> {
+	let segments = parse_pattern(&args.pattern, false)?;
+	let count = segments.iter().filter(|seg| matches!(seg, Segment::Capture(_))).count();
+	if count != args.types.len() {
+		let msg = format!("the pattern has {} captures, but {} types were given", count, args.types.len());
+		return Err(syn::Error::new(args.pattern.span(), msg));
+	}
+
+	let idents: Vec<_> = (0..count).map(|i| format_ident!("__capture{}", i)).collect();
+	let scanner = format_ident!("__scanner");
+	let label = syn::Lifetime::new("'__scan", Span::call_site());
+	let stmts = match_segments(&segments, idents.iter().cloned().zip(args.types.iter()), &scanner, &label);
+
+	let line = &args.input;
+	let value = match &idents[..] {
+		[single] => quote! { #single },
+		idents => quote! { ( #( #idents ),* ) },
+	};
+	Ok(quote! {
+		#label: {
+			let __line: &str = #line;
+			let mut #scanner = ::aoch::parsing::Scanner::new(__line);
+			#stmts
+			::core::result::Result::Ok::<_, ::aoch::parsing::ParseError>(#value)
+		}
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn segments(pattern: &str, named: bool) -> syn::Result<Vec<Segment>> {
+		parse_pattern(&LitStr::new(pattern, proc_macro2::Span::call_site()), named)
+	}
+
+	fn lit(s: &str) -> Segment {
+		Segment::Literal(s.to_string())
+	}
+
+	#[test]
+	fn splits_patterns() {
+		assert_eq!(segments("{}-{} {}: {}", false).unwrap(), [
+			Segment::Capture(None), lit("-"), Segment::Capture(None), lit(" "),
+			Segment::Capture(None), lit(": "), Segment::Capture(None),
+		]);
+		assert_eq!(segments("{{x}} = {count}", true).unwrap(), [lit("{x} = "), Segment::Capture(Some("count".to_string()))]);
+	}
+
+	#[test]
+	fn rejects_invalid_patterns() {
+		assert!(segments("{}{}", false).is_err());
+		assert!(segments("{count}", false).is_err());
+		assert!(segments("{a b}", true).is_err());
+		assert!(segments("{", false).is_err());
+		assert!(segments("}", false).is_err());
+	}
+}
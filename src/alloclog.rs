@@ -1,83 +1,454 @@
 
-use std::{alloc::System, collections::HashMap};
+//! Allocation statistics, and call site reports, of each phase (ie: parsing, or a part) of a run.
+//!
+//! Each phase is measured on the thread running it, so tests running in parallel only see their own allocations. Allocations
+//! made on other threads (ie: those a solution spawns) are not counted.
+//!
+//! Call sites are recorded when `AOCH_ALLOC_SITES` is set, to `1` for every allocation or `N` to sample one in every `N`.
+//! Sites are attributed to the innermost frame from your own code: a function path starting with the running crate's
+//! name, or one of the comma separated prefixes in `AOCH_ALLOC_FILTER` (ie: `day07,common::grid`). `AOCH_ALLOC_TOP` sets how
+//! many sites are listed (10 by default), and `AOCH_ALLOC_FOLDED` is a path to write every recorded stack to, in the folded
+//! format taken by flame graph tools (ie: `inferno-flamegraph`).
 
-use tracking_allocator::{Allocator, AllocationTracker};
+use std::{
+    alloc::System,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    ffi::c_void,
+    fmt,
+    path::Path,
+    sync::{atomic::{AtomicUsize, Ordering}, Mutex, Once},
+};
+
+use colored::Colorize;
+use tracking_allocator::{Allocator, AllocationGroupId, AllocationGroupToken, AllocationRegistry, AllocationTracker};
 
 #[global_allocator]
 static ALLOCATOR: Allocator<System> = Allocator::system();
 
-#[derive(Debug, Clone, Default)]
-pub(crate) struct MemoryStatistics {
-    allocated: HashMap<usize, ()>,
+const SITES_VAR: &str = "AOCH_ALLOC_SITES";
+const FILTER_VAR: &str = "AOCH_ALLOC_FILTER";
+const TOP_VAR: &str = "AOCH_ALLOC_TOP";
+const FOLDED_VAR: &str = "AOCH_ALLOC_FOLDED";
+
+/// Frames kept of each recorded allocation's stack
+const MAX_FRAMES: usize = 64;
+
+/// Record one in every `SAMPLE` allocations' call stack, or none if zero. Set from `AOCH_ALLOC_SITES` on [`install`].
+static SAMPLE: AtomicUsize = AtomicUsize::new(0);
+/// Allocations seen while sampling
+static SEEN: AtomicUsize = AtomicUsize::new(0);
+
+static INSTALL: Once = Once::new();
+
+/// Each allocation group that is currently being measured, from any thread
+static GROUPS: Mutex<Vec<Group>> = Mutex::new(Vec::new());
+
+thread_local! {
+    /// Phases measured on this thread since the last summary
+    static PHASES: RefCell<Vec<Phase>> = const { RefCell::new(Vec::new()) };
+    /// Record the call stack of every allocation on this thread, regardless of sampling
+    static RECORD_ALL: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Recorded call stacks (instruction pointers, innermost first) with the number of allocations and bytes made from them.
+/// Symbols are only resolved when reporting, as that is far slower than walking the stack.
+type Stacks = HashMap<Vec<usize>, (usize, usize)>;
+
+struct Group {
+    id: AllocationGroupId,
+    stats: AllocStats,
+    stacks: Stacks,
+}
+
+struct Phase {
+    name: String,
+    stats: AllocStats,
+    stacks: Stacks,
 }
 
+/// Allocation statistics of a phase (ie: parsing, or a part)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AllocStats {
+    /// Number of allocations made
+    pub count: usize,
+    /// Bytes allocated in total
+    pub total_bytes: usize,
+    /// Bytes allocated, and not yet freed, at the end of the phase
+    pub live_bytes: usize,
+    /// Most bytes allocated at once during the phase
+    pub peak_bytes: usize,
+}
+
+impl AllocStats {
+    /// Combines the statistics of several runs of the same phase
+    fn merge(&mut self, other: &AllocStats) {
+        self.count += other.count;
+        self.total_bytes += other.total_bytes;
+        self.live_bytes = self.live_bytes.max(other.live_bytes);
+        self.peak_bytes = self.peak_bytes.max(other.peak_bytes);
+    }
+}
+
+/// Formatted as `12 allocs, 3.4 KiB total, 1.2 KiB peak, 512 B live`
+impl fmt::Display for AllocStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} allocs, {} total, {} peak, {} live",
+            self.count, Bytes(self.total_bytes), Bytes(self.peak_bytes), Bytes(self.live_bytes),
+        )
+    }
+}
+
+struct Bytes(usize);
+
+impl fmt::Display for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+        if self.0 < 1024 {
+            return write!(f, "{} B", self.0);
+        }
+        let mut size = self.0 as f64 / 1024.0;
+        let mut unit = 0;
+        while size >= 1024.0 && unit < UNITS.len() - 1 {
+            size /= 1024.0;
+            unit += 1;
+        }
+        write!(f, "{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Counts allocations made within a measured group, recording their call stacks if sampling. Anything outside of one (the
+/// root group) is ignored.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct MemoryStatistics;
+
 impl AllocationTracker for MemoryStatistics {
     fn allocated(
         &self,
-        addr: usize,
+        _addr: usize,
         object_size: usize,
-        wrapped_size: usize,
-        group_id: tracking_allocator::AllocationGroupId,
+        _wrapped_size: usize,
+        group_id: AllocationGroupId,
     ) {
-        let mut i = 0;
-        let mut matching_frames = Vec::with_capacity(4);
-        backtrace::trace(|frame| {
-            // let ip = frame.ip();
-            // let symbol_address = frame.symbol_address();
-
-            let mut name = None;
-            let mut fname = None;
-            let mut lineno = None;
-            let mut colno = None;
-
-            // Resolve this instruction pointer to a symbol name
-            backtrace::resolve_frame(frame, |sym| {
-                name = sym.name().map(|n| n.to_string());
-                fname = sym.filename().map(|f| f.to_owned());
-                lineno = sym.lineno();
-                colno = sym.colno();
-            });
+        if group_id == AllocationGroupId::ROOT {
+            return;
+        }
+        // walked before taking the lock, so that other threads' allocations aren't held up by it
+        let stack = sampled().then(capture_stack);
 
-            let cont = if let Some(func) = name {
-                if func.contains("day") {
-                    let label = match (fname, lineno, colno) {
-                        (Some(file), Some(line), Some(col)) =>
-                            format!("{func}@{}:{line:}:{col:}", file.display()),
-                        (Some(file), Some(line), _) =>
-                            format!("{func}@{}:{line:}", file.display()),
-                        (Some(file), _, _) =>
-                            format!("{func}@{}", file.display()),
-                        (_, _, _) =>
-                            format!("{func}"),
-                    };
-
-                    matching_frames.push((i, func, format!("memory allocation @{:p} (frame idx[{}]) {}", frame.ip(), i, label)));
-                    true
-                } else {
-                    true
-                }
-            } else {
-                true
-            };
-
-            i += 1;
-            cont // keep going to the next frame
-        });
-
-        matching_frames.iter()
-            .enumerate()
-            .for_each(|(li, (_fi, _func, logmsg))| {
-                eprintln!("[{}] {}", li, logmsg);
-            });
+        let mut groups = GROUPS.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(group) = groups.iter_mut().find(|group| group.id == group_id) {
+            let stats = &mut group.stats;
+            stats.count += 1;
+            stats.total_bytes += object_size;
+            stats.live_bytes += object_size;
+            stats.peak_bytes = stats.peak_bytes.max(stats.live_bytes);
+
+            if let Some(stack) = stack {
+                let (count, bytes) = group.stacks.entry(stack).or_default();
+                *count += 1;
+                *bytes += object_size;
+            }
+        }
     }
+
     fn deallocated(
         &self,
-        addr: usize,
+        _addr: usize,
         object_size: usize,
-        wrapped_size: usize,
-        source_group_id: tracking_allocator::AllocationGroupId,
-        current_group_id: tracking_allocator::AllocationGroupId,
+        _wrapped_size: usize,
+        source_group_id: AllocationGroupId,
+        _current_group_id: AllocationGroupId,
     ) {
+        // charged to the group that made the allocation
+        if source_group_id == AllocationGroupId::ROOT {
+            return;
+        }
+        let mut groups = GROUPS.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(group) = groups.iter_mut().find(|group| group.id == source_group_id) {
+            group.stats.live_bytes = group.stats.live_bytes.saturating_sub(object_size);
+        }
+    }
+}
+
+/// Installs the allocation tracker and enables tracking, the first time it is called in this process
+pub(crate) fn install() {
+    INSTALL.call_once(|| {
+        let sample = std::env::var(SITES_VAR).ok().and_then(|v| v.trim().parse().ok()).unwrap_or(0);
+        SAMPLE.store(sample, Ordering::Relaxed);
+        if AllocationRegistry::set_global_tracker(MemoryStatistics).is_err() {
+            eprintln!("{}", "warning: another allocation tracker is already installed - allocations will not be measured".yellow());
+            return;
+        }
+        AllocationRegistry::enable_tracking();
+    });
+}
+
+/// Whether to record the current allocation's call stack
+// `is_multiple_of` needs a newer Rust than the crate otherwise does
+#[allow(unknown_lints, clippy::manual_is_multiple_of)]
+fn sampled() -> bool {
+    if RECORD_ALL.try_with(Cell::get).unwrap_or(false) {
+        return true;
+    }
+    match SAMPLE.load(Ordering::Relaxed) {
+        0 => false,
+        n => SEEN.fetch_add(1, Ordering::Relaxed) % n == 0,
+    }
+}
+
+fn capture_stack() -> Vec<usize> {
+    let mut stack = Vec::with_capacity(MAX_FRAMES);
+    backtrace::trace(|frame| {
+        stack.push(frame.ip() as usize);
+        stack.len() < MAX_FRAMES
+    });
+    stack
+}
+
+/// Runs `f` in its own allocation group, returning the allocations it made
+pub fn measure<R>(f: impl FnOnce() -> R) -> (R, AllocStats) {
+    let (res, group) = measure_group(f);
+    (res, group.stats)
+}
+
+fn measure_group<R>(f: impl FnOnce() -> R) -> (R, Group) {
+    let mut token = AllocationGroupToken::register().expect("unable to register memory allocator tracking");
+    let id = token.id();
+    // registered before entering the group, so that the tracker never allocates within it
+    AllocationRegistry::untracked(|| {
+        GROUPS.lock().unwrap_or_else(|e| e.into_inner()).push(Group {
+            id: id.clone(),
+            stats: AllocStats::default(),
+            stacks: HashMap::new(),
+        })
+    });
+
+    let guard = token.enter();
+    let res = f();
+    guard.exit();
+
+    let group = AllocationRegistry::untracked(|| {
+        let mut groups = GROUPS.lock().unwrap_or_else(|e| e.into_inner());
+        let i = groups.iter().position(|group| group.id == id).expect("allocation group went missing");
+        groups.swap_remove(i)
+    });
+    (res, group)
+}
+
+/// Measures `f` as a named phase, to be included in the next [`summary`]
+pub(crate) fn phase<R>(name: &str, f: impl FnOnce() -> R) -> R {
+    let (res, group) = measure_group(f);
+    AllocationRegistry::untracked(|| {
+        let phase = Phase { name: name.to_string(), stats: group.stats, stacks: group.stacks };
+        PHASES.with(|phases| phases.borrow_mut().push(phase));
+    });
+    res
+}
+
+fn merge_stacks(into: &mut Stacks, from: &Stacks) {
+    for (stack, (count, bytes)) in from {
+        let entry = into.entry(stack.clone()).or_default();
+        entry.0 += count;
+        entry.1 += bytes;
+    }
+}
+
+/// Runs `f`, returning the allocations made while solving: the phases it ran other than parsing (ie: the part of a
+/// [`test_part`](crate::test_part) case), or all of `f` if it ran none (ie: a [`run_test`](crate::run_test) function)
+pub(crate) fn measure_solve<R>(f: impl FnOnce() -> R) -> (R, AllocStats) {
+    let (res, stats, _) = solve_phases(false, f);
+    (res, stats)
+}
+
+/// Runs `f` again while recording every allocation's call stack, returning the `top` sites it allocated most from. Its
+/// phases are left out of the summary, as they were already measured.
+pub(crate) fn top_sites<R>(f: impl FnOnce() -> R, top: usize) -> String {
+    let start = PHASES.with(|phases| phases.borrow().len());
+    let (_, _, stacks) = solve_phases(true, f);
+    AllocationRegistry::untracked(|| {
+        PHASES.with(|phases| phases.borrow_mut().truncate(start));
+        site_table(&mut Resolver::default(), &stacks, top, 1)
+    })
+}
+
+fn solve_phases<R>(record_all: bool, f: impl FnOnce() -> R) -> (R, AllocStats, Stacks) {
+    let start = PHASES.with(|phases| phases.borrow().len());
+    let was_recording = RECORD_ALL.with(|record| record.replace(record_all));
+    let (res, whole) = measure_group(f);
+    RECORD_ALL.with(|record| record.set(was_recording));
+
+    AllocationRegistry::untracked(|| {
+        PHASES.with(|phases| {
+            let phases = phases.borrow();
+            let mut solving = phases[start..].iter().filter(|phase| phase.name != "parse").peekable();
+            if solving.peek().is_none() {
+                return (res, whole.stats, whole.stacks);
+            }
+            let mut stats = AllocStats::default();
+            let mut stacks = Stacks::new();
+            for phase in solving {
+                stats.merge(&phase.stats);
+                merge_stacks(&mut stacks, &phase.stacks);
+            }
+            (res, stats, stacks)
+        })
+    })
+}
+
+/// Summarizes the phases measured on this thread since the last summary, combining repeated phases (ie: each test case's parse),
+/// followed by the top allocation sites if they were recorded
+pub(crate) fn summary(title: &str) -> String {
+    AllocationRegistry::untracked(|| {
+        let phases = PHASES.with(|phases| std::mem::take(&mut *phases.borrow_mut()));
+        let mut merged: Vec<(String, AllocStats, usize)> = Vec::new();
+        let mut stacks = Stacks::new();
+        for phase in phases {
+            merge_stacks(&mut stacks, &phase.stacks);
+            match merged.iter_mut().find(|(n, _, _)| *n == phase.name) {
+                Some((_, total, runs)) => {
+                    total.merge(&phase.stats);
+                    *runs += 1;
+                },
+                None => merged.push((phase.name, phase.stats, 1)),
+            }
+        }
+
+        let width = merged.iter().map(|(name, _, _)| name.len()).max().unwrap_or(0);
+        let mut out = format!("{}:", title);
+        for (name, stats, runs) in merged {
+            let runs = if runs > 1 { format!(" (over {} runs)", runs) } else { String::new() };
+            out += &format!("\n  {:width$}  {}{}", name, stats, runs, width = width);
+        }
+
+        let sample = SAMPLE.load(Ordering::Relaxed);
+        if sample > 0 {
+            let mut resolver = Resolver::default();
+            let top = std::env::var(TOP_VAR).ok().and_then(|v| v.trim().parse().ok()).unwrap_or(10);
+            out += "\n";
+            out += &site_table(&mut resolver, &stacks, top, sample);
+            if let Some(path) = std::env::var_os(FOLDED_VAR) {
+                let written = std::fs::write(&path, folded(&mut resolver, &stacks));
+                match written {
+                    Ok(()) => out += &format!("\nwrote allocation stacks to {}", Path::new(&path).display()),
+                    Err(e) => out += &format!("\nunable to write allocation stacks to {}: {}", Path::new(&path).display(), e),
+                }
+            }
+        }
+        out
+    })
+}
+
+/// A resolved frame. Inlined functions resolve to several for the same instruction pointer.
+#[derive(Clone)]
+struct Symbol {
+    /// Demangled, without its hash. (ie: `day07::Day07::part1`)
+    name: String,
+    /// `file:line`, relative to the crate if within it
+    location: Option<String>,
+}
+
+/// Resolves instruction pointers to symbols, caching them
+#[derive(Default)]
+struct Resolver {
+    cache: HashMap<usize, Vec<Symbol>>,
+    filters: Option<Vec<String>>,
+}
+
+impl Resolver {
+    fn resolve(&mut self, ip: usize) -> &[Symbol] {
+        self.cache.entry(ip).or_insert_with(|| {
+            let manifest = std::env::var_os("CARGO_MANIFEST_DIR");
+            let mut symbols = Vec::new();
+            backtrace::resolve(ip as *mut c_void, |sym| {
+                let name = match sym.name() {
+                    Some(name) => format!("{:#}", name),
+                    None => return,
+                };
+                let location = sym.filename().map(|file| {
+                    let file = manifest.as_ref().and_then(|dir| file.strip_prefix(dir).ok()).unwrap_or(file);
+                    match sym.lineno() {
+                        Some(line) => format!("{}:{}", file.display(), line),
+                        None => file.display().to_string(),
+                    }
+                });
+                symbols.push(Symbol { name, location });
+            });
+            symbols
+        })
+    }
+
+    /// Prefixes of the function paths counted as your own code
+    fn filters(&mut self) -> &[String] {
+        self.filters.get_or_insert_with(|| {
+            let filters = std::env::var(FILTER_VAR).ok()
+                .or_else(|| std::env::var("CARGO_PKG_NAME").ok().map(|name| name.replace('-', "_")))
+                .unwrap_or_else(|| "day".to_string());
+            filters.split(',').map(|f| f.trim().to_string()).filter(|f| ! f.is_empty()).collect()
+        })
+    }
+
+    /// The innermost symbol of `stack` from your own code, as `name (file:line)`
+    fn site(&mut self, stack: &[usize]) -> Option<String> {
+        let filters = self.filters().to_vec();
+        stack.iter().find_map(|&ip| {
+            self.resolve(ip).iter()
+                .find(|sym| {
+                    // trait impls are written as `<day07::Day07 as aoch::AoCDay>::part1`
+                    let name = sym.name.trim_start_matches('<');
+                    filters.iter().any(|f| name.starts_with(f.as_str()))
+                })
+                .map(|sym| match &sym.location {
+                    Some(location) => format!("{} ({})", sym.name, location),
+                    None => sym.name.clone(),
+                })
+        })
+    }
+}
+
+/// Whether a symbol is part of recording an allocation, rather than what made it
+fn is_allocator(sym: &Symbol) -> bool {
+    const PREFIXES: [&str; 8] = [
+        "backtrace::", "tracking_allocator::", "<tracking_allocator::", "aoch::alloclog::",
+        "__rust_alloc", "__rust_realloc", "__rdl_", "alloc::alloc::",
+    ];
+    PREFIXES.iter().any(|p| sym.name.starts_with(p))
+}
+
+/// The `top` sites that allocated the most bytes, from stacks sampled one in every `sample` allocations
+fn site_table(resolver: &mut Resolver, stacks: &Stacks, top: usize, sample: usize) -> String {
+    let mut sites: HashMap<String, (usize, usize)> = HashMap::new();
+    for (stack, (count, bytes)) in stacks {
+        let site = resolver.site(stack).unwrap_or_else(|| "(outside of filtered code)".to_string());
+        let entry = sites.entry(site).or_default();
+        entry.0 += count;
+        entry.1 += bytes;
+    }
+    let mut sites: Vec<_> = sites.into_iter().collect();
+    sites.sort_by(|(a_site, (_, a_bytes)), (b_site, (_, b_bytes))| b_bytes.cmp(a_bytes).then(a_site.cmp(b_site)));
+
+    let sampled = if sample > 1 { format!(", sampled 1 in {}", sample) } else { String::new() };
+    let mut out = format!("allocation sites (top {} by bytes{}):", top.min(sites.len()), sampled);
+    out += &format!("\n  {:>8}  {:>10}  site", "allocs", "bytes");
+    for (site, (count, bytes)) in sites.iter().take(top) {
+        out += &format!("\n  {:>8}  {:>10}  {}", count, Bytes(*bytes).to_string(), site);
+    }
+    out
+}
 
+/// Every recorded stack as `outermost;...;innermost bytes`, the format taken by flame graph tools
+fn folded(resolver: &mut Resolver, stacks: &Stacks) -> String {
+    let mut lines: HashMap<String, usize> = HashMap::new();
+    for (stack, (_, bytes)) in stacks {
+        let mut names: Vec<Symbol> = stack.iter().flat_map(|&ip| resolver.resolve(ip).to_vec()).collect();
+        let own = names.iter().position(|sym| ! is_allocator(sym)).unwrap_or(names.len());
+        names.drain(..own);
+        let line: Vec<&str> = names.iter().rev().map(|sym| sym.name.as_str()).collect();
+        *lines.entry(line.join(";")).or_default() += bytes;
     }
+    let mut lines: Vec<_> = lines.into_iter().collect();
+    lines.sort();
+    lines.into_iter().map(|(stack, bytes)| format!("{} {}\n", stack, bytes)).collect()
 }
@@ -1,26 +1,130 @@
+use std::any::Any;
 use std::fmt;
-use std::panic::UnwindSafe;
+use std::marker::PhantomData;
+use std::panic::{self, UnwindSafe};
 use std::str;
 
+use crate::Params;
+
 pub trait AoCDay: fmt::Debug + Sized + UnwindSafe + Clone + Copy {
 	type Data<'i>: fmt::Debug;
-	type Answer: fmt::Debug + fmt::Display + PartialEq + Eq;
+
+	/// The day's answer type(s).
+	///
+	/// A single type (ie: `usize`) is used for both parts. Use [`Answers<A1, A2>`] to give each part its own type.
+	type Answer: AnswerTypes;
 
 	/// The day's number.
 	fn day(&self) -> u8;
 
 	/// Parses the raw input into a useable format. Input validation is recommended, and should be clonable for multiple uses.
+	///
+	/// Days with puzzle parameters should also implement [`AoCDay::parse_with`], and have this call it with `&Params::new()`.
 	fn parse<'i>(&self, input: &'i str) -> Self::Data<'i>;
 
+	/// Parses the raw input, given puzzle parameters that differ between the examples and the real input.
+	///
+	/// Parameters that aren't set should default to the real input's values. Any needed by the parts should be kept in the Data struct.
+	///
+	/// Defaults to [`AoCDay::parse`], ignoring the parameters.
+	fn parse_with<'i>(&self, input: &'i str, _params: &Params) -> Self::Data<'i> {
+		self.parse(input)
+	}
+
 	/// Part 1 implementation.
-	fn part1(&self, data: &mut Self::Data<'_>) -> Self::Answer;
+	///
+	/// Left unimplemented, the part is reported as [`unsolved`].
+	fn part1(&self, _data: &mut Self::Data<'_>) -> Answer1<Self> {
+		unsolved()
+	}
 
 	/// Part 2 implementation.
 	///
-	/// If the result of part1 is needed for part2, then it should be recomputed, or stored in the Data struct.
-	fn part2(&self, data: &mut Self::Data<'_>) -> Self::Answer;
+	/// If the result of part1 is needed for part2, implement [`AoCDay::part2_with`] instead.
+	///
+	/// Left unimplemented (ie: for day 25), the part is reported as [`unsolved`].
+	fn part2(&self, _data: &mut Self::Data<'_>) -> Answer2<Self> {
+		unsolved()
+	}
+
+	/// Set to have part 2 implemented by [`AoCDay::part2_with`], receiving part 1's answer.
+	///
+	/// Runners will then always run part 1 before part 2, even if only part 2 was requested.
+	const PART2_USES_PART1: bool = false;
+
+	/// Part 2 implementation, given the answer from part 1. Only used if [`AoCDay::PART2_USES_PART1`] is set.
+	///
+	/// Any intermediate state from part 1 can be stored in the Data struct, which is shared between both parts.
+	fn part2_with(&self, data: &mut Self::Data<'_>, _part1: Answer1<Self>) -> Answer2<Self> {
+		self.part2(data)
+	}
+}
+
+/// Marks a part as not solved yet, for use in place of `todo!()`.
+///
+/// Runners report the part as unsolved instead of crashing, and `test_runner` skips its cases instead of failing.
+pub fn unsolved() -> ! {
+	// resume_unwind skips the panic hook, so no panic message is printed
+	panic::resume_unwind(Box::new(Unsolved))
+}
+
+/// The panic payload used by [`unsolved`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unsolved;
+
+impl fmt::Display for Unsolved {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "not solved yet")
+	}
+}
+
+/// Extracts the message from a panic payload, as caught by [`panic::catch_unwind`]
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+	if let Some(s) = payload.downcast_ref::<&str>() {
+		s.to_string()
+	} else if let Some(s) = payload.downcast_ref::<String>() {
+		s.clone()
+	} else if payload.is::<Unsolved>() {
+		"not solved yet".to_string()
+	} else {
+		"<non-string panic payload>".to_string()
+	}
 }
 
+/// Maps an [`AoCDay::Answer`] to the answer type of each part.
+///
+/// Implemented for any single answer type (used for both parts), and for [`Answers<A1, A2>`].
+pub trait AnswerTypes {
+	type Answer1: fmt::Debug + fmt::Display + PartialEq + Eq;
+	type Answer2: fmt::Debug + fmt::Display + PartialEq + Eq;
+}
+
+impl<T: fmt::Debug + fmt::Display + PartialEq + Eq> AnswerTypes for T {
+	type Answer1 = T;
+	type Answer2 = T;
+}
+
+/// Used as an [`AoCDay::Answer`] when part 1 and part 2 have different answer types.
+///
+/// ```ignore
+/// type Answer = Answers<usize, String>;
+/// ```
+pub struct Answers<A1, A2>(PhantomData<(A1, A2)>);
+
+impl<A1, A2> AnswerTypes for Answers<A1, A2>
+where
+	A1: fmt::Debug + fmt::Display + PartialEq + Eq,
+	A2: fmt::Debug + fmt::Display + PartialEq + Eq,
+{
+	type Answer1 = A1;
+	type Answer2 = A2;
+}
+
+/// The answer type of a day's part 1.
+pub type Answer1<D> = <<D as AoCDay>::Answer as AnswerTypes>::Answer1;
+/// The answer type of a day's part 2.
+pub type Answer2<D> = <<D as AoCDay>::Answer as AnswerTypes>::Answer2;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum DayPart {
 	Part1,
@@ -34,3 +138,113 @@ impl fmt::Display for DayPart {
 		}
 	}
 }
+
+/// A type-level [`DayPart`], so that test cases can be checked against that part's answer type.
+pub trait Part: fmt::Debug + Copy {
+	/// The runtime equivalent of this part.
+	const PART: DayPart;
+
+	/// This part's answer type, for a given [`AoCDay::Answer`].
+	type Answer<A: AnswerTypes>: fmt::Debug + fmt::Display + PartialEq + Eq;
+
+	/// Runs this part of `day` against already parsed data.
+	fn solve<D: AoCDay>(self, day: D, data: &mut D::Data<'_>) -> Self::Answer<D::Answer>;
+}
+
+/// Selects [`AoCDay::part1`] and its answer type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Part1;
+
+/// Selects [`AoCDay::part2`] and its answer type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Part2;
+
+impl Part for Part1 {
+	const PART: DayPart = DayPart::Part1;
+	type Answer<A: AnswerTypes> = A::Answer1;
+
+	fn solve<D: AoCDay>(self, day: D, data: &mut D::Data<'_>) -> Self::Answer<D::Answer> {
+		day.part1(data)
+	}
+}
+
+impl Part for Part2 {
+	const PART: DayPart = DayPart::Part2;
+	type Answer<A: AnswerTypes> = A::Answer2;
+
+	fn solve<D: AoCDay>(self, day: D, data: &mut D::Data<'_>) -> Self::Answer<D::Answer> {
+		if D::PART2_USES_PART1 {
+			let part1 = day.part1(data);
+			day.part2_with(data, part1)
+		} else {
+			day.part2(data)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::cell::Cell;
+
+	thread_local! {
+		static PART1_RUNS: Cell<usize> = const { Cell::new(0) };
+	}
+
+	#[derive(Debug, Clone, Copy)]
+	struct Day<const USES_PART1: bool>;
+
+	impl<const USES_PART1: bool> AoCDay for Day<USES_PART1> {
+		type Data<'i> = u32;
+		type Answer = Answers<u32, String>;
+		const PART2_USES_PART1: bool = USES_PART1;
+
+		fn day(&self) -> u8 {
+			1
+		}
+		fn parse(&self, input: &str) -> u32 {
+			input.parse().unwrap()
+		}
+		fn part1(&self, data: &mut u32) -> u32 {
+			PART1_RUNS.with(|runs| runs.set(runs.get() + 1));
+			*data * 2
+		}
+		fn part2(&self, data: &mut u32) -> String {
+			format!("alone {}", data)
+		}
+		fn part2_with(&self, _data: &mut u32, part1: u32) -> String {
+			format!("after {}", part1)
+		}
+	}
+
+	fn solve2<D: AoCDay<Data<'static> = u32>>(day: D) -> (Answer2<D>, usize) {
+		PART1_RUNS.with(|runs| runs.set(0));
+		let answer = Part2.solve(day, &mut 5);
+		(answer, PART1_RUNS.with(Cell::get))
+	}
+
+	#[test]
+	fn part2_runs_part1_first() {
+		assert_eq!(solve2(Day::<true>), ("after 10".to_string(), 1));
+		assert_eq!(solve2(Day::<false>), ("alone 5".to_string(), 0));
+		assert_eq!(Part1.solve(Day::<true>, &mut 5), 10);
+	}
+
+	#[test]
+	fn unsolved_parts() {
+		#[derive(Debug, Clone, Copy)]
+		struct Empty;
+		impl AoCDay for Empty {
+			type Data<'i> = ();
+			type Answer = u32;
+			fn day(&self) -> u8 {
+				25
+			}
+			fn parse(&self, _input: &str) {}
+		}
+
+		let payload = panic::catch_unwind(|| Empty.part2(&mut ())).unwrap_err();
+		assert!(payload.is::<Unsolved>());
+		assert_eq!(panic_message(&*payload), "not solved yet");
+	}
+}
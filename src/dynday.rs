@@ -0,0 +1,430 @@
+use std::fmt;
+use std::panic;
+use std::time::{Duration, Instant};
+
+use crate::aocday::{panic_message, Unsolved};
+use crate::{AoCDay, DayPart, Params};
+
+/// An object-safe view of an [`AoCDay`], with stringified answers.
+///
+/// Blanket implemented for every `AoCDay`, so days can be stored as `&dyn DynDay` or `Box<dyn DynDay>`.
+pub trait DynDay {
+	/// The day's number.
+	fn day(&self) -> u8;
+
+	/// The name of this implementation, to tell apart several variants of the same day.
+	fn name(&self) -> String;
+
+	/// Parses `input` once, then runs the requested part against it (or both parts, if `None`).
+	///
+	/// Panics while parsing or solving are captured in the report.
+	fn run(&self, part: Option<DayPart>, input: &str) -> RunReport {
+		self.run_with(part, input, &Params::new())
+	}
+
+	/// As [`DynDay::run`], with puzzle parameters that differ from the real input's.
+	fn run_with(&self, part: Option<DayPart>, input: &str, params: &Params) -> RunReport;
+}
+
+impl<D: AoCDay> DynDay for D {
+	fn day(&self) -> u8 {
+		AoCDay::day(self)
+	}
+
+	fn name(&self) -> String {
+		format!("{:?}", self)
+	}
+
+	fn run_with(&self, part: Option<DayPart>, input: &str, params: &Params) -> RunReport {
+		let day = *self;
+
+		let start = Instant::now();
+		let parsed = panic::catch_unwind(move || crate::alloc_phase("parse", || day.parse_with(input, params)));
+		let parse_elapsed = start.elapsed();
+
+		let (parse_panic, parts) = match parsed {
+			Ok(mut data) => {
+				let one = matches!(part, None | Some(DayPart::Part1));
+				let two = matches!(part, None | Some(DayPart::Part2));
+				(None, run_parts(day, &mut data, one, two))
+			},
+			Err(e) => (Some(panic_message(&*e)), Vec::new()),
+		};
+
+		RunReport {
+			day: AoCDay::day(self),
+			parse_elapsed,
+			parse_panic,
+			parts,
+		}
+	}
+}
+
+/// The results of parsing a day's input, and running its parts against it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunReport {
+	pub day: u8,
+	/// Time taken to parse the input.
+	pub parse_elapsed: Duration,
+	/// The panic message, if parsing panicked. No parts are run in that case.
+	pub parse_panic: Option<String>,
+	/// The parts that were run, in order.
+	pub parts: Vec<PartReport>,
+}
+
+/// The result of running a single part.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartReport {
+	pub part: DayPart,
+	pub outcome: PartOutcome,
+	/// Time taken by this part.
+	pub elapsed: Duration,
+	/// Time taken running part 1 on this part's behalf, if part 1 wasn't requested itself. (See [`AoCDay::PART2_USES_PART1`])
+	pub part1_elapsed: Option<Duration>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PartOutcome {
+	/// The part's answer, rendered with `Display`.
+	Solved(String),
+	/// The part called [`crate::unsolved`], or is left unimplemented.
+	Unsolved,
+	/// The part panicked, with the given panic message.
+	Panicked(String),
+}
+
+impl PartOutcome {
+	/// The answer, if solved.
+	pub fn answer(&self) -> Option<&str> {
+		match self {
+			PartOutcome::Solved(ans) => Some(ans),
+			_ => None,
+		}
+	}
+}
+
+impl RunReport {
+	/// The report for `part`, if it was run.
+	pub fn part(&self, part: DayPart) -> Option<&PartReport> {
+		self.parts.iter().find(|p| p.part == part)
+	}
+}
+
+impl fmt::Display for RunReport {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if let Some(msg) = &self.parse_panic {
+			writeln!(f, "Day {} panicked while parsing: {}", self.day, msg)?;
+		}
+		for part in &self.parts {
+			part.write_line(f, self.day)?;
+		}
+		Ok(())
+	}
+}
+
+impl PartReport {
+	/// Writes this part's answer and timing as a line
+	pub(crate) fn write_line(&self, f: &mut dyn fmt::Write, day: u8) -> fmt::Result {
+		let timing = match self.part1_elapsed {
+			None => format!("{:?}", self.elapsed),
+			Some(p1) => format!("{:?}, +{:?} for part 1", self.elapsed, p1),
+		};
+		match &self.outcome {
+			PartOutcome::Solved(ans) => writeln!(f, "Day {} {}: {} ({})", day, self.part, ans, timing),
+			// day 25 only has the one part
+			PartOutcome::Unsolved if day == 25 && self.part == DayPart::Part2 => Ok(()),
+			PartOutcome::Unsolved => writeln!(f, "Day {} {}: not solved yet", day, self.part),
+			PartOutcome::Panicked(msg) => writeln!(f, "Day {} {}: panicked: {}", day, self.part, msg),
+		}
+	}
+}
+
+/// Compares the reports of several variants of the same day, which were run against the same input.
+///
+/// For each part, the `Display` impl writes the agreed upon answer (or that the variants disagree), followed by each variant's timing.
+pub struct VariantComparison<'r> {
+	pub names: Vec<String>,
+	pub reports: &'r [RunReport],
+}
+
+impl VariantComparison<'_> {
+	/// The parts that variants disagree on.
+	pub fn disagreements(&self) -> Vec<DayPart> {
+		[DayPart::Part1, DayPart::Part2].iter().copied().filter(|&part| ! self.agrees(part)).collect()
+	}
+
+	/// Whether all variants that ran `part` came to the same answer, or all panicked (taken to agree that the input is invalid).
+	///
+	/// A variant panicking (while parsing or solving) where another solves the part disagrees with it. Unsolved variants are left out.
+	pub fn agrees(&self, part: DayPart) -> bool {
+		// the answer, or None for a panic
+		let mut outcomes = self.reports.iter().filter_map(|r| match r.part(part).map(|p| &p.outcome) {
+			_ if r.parse_panic.is_some() => Some(None),
+			Some(PartOutcome::Solved(ans)) => Some(Some(ans)),
+			Some(PartOutcome::Panicked(_)) => Some(None),
+			Some(PartOutcome::Unsolved) | None => None,
+		});
+		match outcomes.next() {
+			None => true,
+			Some(first) => outcomes.all(|outcome| outcome == first),
+		}
+	}
+}
+
+impl fmt::Display for VariantComparison<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let day = match self.reports.first() {
+			None => return Ok(()),
+			Some(r) => r.day,
+		};
+		let width = self.names.iter().map(String::len).max().unwrap_or(0);
+
+		for (name, report) in self.names.iter().zip(self.reports) {
+			if let Some(msg) = &report.parse_panic {
+				writeln!(f, "Day {} ({}) panicked while parsing: {}", day, name, msg)?;
+			}
+		}
+
+		for part in [DayPart::Part1, DayPart::Part2] {
+			let runs: Vec<(&String, &PartReport)> = self.names.iter()
+				.zip(self.reports)
+				.filter_map(|(name, r)| r.part(part).map(|p| (name, p)))
+				.collect();
+
+			let answer = runs.iter().find_map(|(_, p)| p.outcome.answer());
+			let agrees = self.agrees(part);
+			match answer {
+				// day 25 only has the one part
+				None if day == 25 && part == DayPart::Part2 => continue,
+				None if runs.is_empty() => continue,
+				None => writeln!(f, "Day {} {}: not solved yet", day, part)?,
+				Some(ans) if agrees => writeln!(f, "Day {} {}: {}", day, part, ans)?,
+				Some(_) => writeln!(f, "Day {} {}: variants disagree!", day, part)?,
+			}
+
+			let fastest = runs.iter()
+				.filter(|(_, p)| p.outcome.answer().is_some())
+				.map(|(_, p)| p.elapsed + p.part1_elapsed.unwrap_or_default())
+				.min();
+
+			for (name, p) in &runs {
+				let total = p.elapsed + p.part1_elapsed.unwrap_or_default();
+				let outcome = match &p.outcome {
+					PartOutcome::Solved(ans) if ! agrees => format!("{} ", ans),
+					PartOutcome::Solved(_) => String::new(),
+					PartOutcome::Unsolved => "not solved yet ".to_string(),
+					PartOutcome::Panicked(msg) => format!("panicked: {} ", msg),
+				};
+				let relative = match fastest {
+					Some(fastest) if p.outcome.answer().is_some() && fastest < total && ! fastest.is_zero() => {
+						format!(", {:.1}x slower", total.as_secs_f64() / fastest.as_secs_f64())
+					},
+					_ => String::new(),
+				};
+				writeln!(f, "\t{:width$}  {}({:?}{})", name, outcome, total, relative, width = width)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// The error from [`crate::run_variants`] when variants came to different answers, with every variant's report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariantsDisagree {
+	pub day: u8,
+	/// The parts that variants disagree on.
+	pub parts: Vec<DayPart>,
+	pub reports: Vec<RunReport>,
+}
+
+impl fmt::Display for VariantsDisagree {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let parts: Vec<String> = self.parts.iter().map(DayPart::to_string).collect();
+		write!(f, "day {} variants disagree on {}", self.day, parts.join(" and "))
+	}
+}
+
+impl std::error::Error for VariantsDisagree {}
+
+/// Runs the selected parts against already parsed data, capturing their answers, timings and panics.
+///
+/// If part 2 uses part 1's answer, part 1 is run first regardless. Its time is then reported alongside part 2's, if part 1 wasn't requested itself.
+pub(crate) fn run_parts<D: AoCDay>(day: D, data: &mut D::Data<'_>, one: bool, two: bool) -> Vec<PartReport> {
+	let mut reports = Vec::with_capacity(2);
+
+	let mut part1 = None;
+	if one || (two && D::PART2_USES_PART1) {
+		let (ans, elapsed) = timed(|| crate::alloc_phase("Part 1", || day.part1(data)));
+		if one {
+			reports.push(PartReport {
+				part: DayPart::Part1,
+				outcome: outcome(ans.as_ref()),
+				elapsed,
+				part1_elapsed: None,
+			});
+		}
+		part1 = Some((ans, elapsed));
+	}
+
+	if two {
+		// only charge part 2 for part 1's time if part 1 ran on its behalf
+		let part1_elapsed = part1.as_ref()
+			.filter(|_| ! one)
+			.map(|&(_, elapsed)| elapsed);
+
+		let (outcome, elapsed) = match part1 {
+			Some((Ok(p1), _)) if D::PART2_USES_PART1 => {
+				let (ans, elapsed) = timed(|| crate::alloc_phase("Part 2", || day.part2_with(data, p1)));
+				(outcome(ans.as_ref()), elapsed)
+			},
+			// cannot run part 2 without part 1's answer, and part 1 may not have been shown
+			Some((Err(PartOutcome::Panicked(msg)), _)) if D::PART2_USES_PART1 => {
+				(PartOutcome::Panicked(format!("part 1 panicked: {}", msg)), Duration::ZERO)
+			},
+			Some((Err(_), _)) if D::PART2_USES_PART1 => (PartOutcome::Unsolved, Duration::ZERO),
+			_ => {
+				let (ans, elapsed) = timed(|| crate::alloc_phase("Part 2", || day.part2(data)));
+				(outcome(ans.as_ref()), elapsed)
+			},
+		};
+		reports.push(PartReport {
+			part: DayPart::Part2,
+			outcome,
+			elapsed,
+			part1_elapsed,
+		});
+	}
+
+	reports
+}
+
+/// Times `f`, catching any panic
+fn timed<R>(f: impl FnOnce() -> R) -> (Result<R, PartOutcome>, Duration) {
+	let start = Instant::now();
+	let res = panic::catch_unwind(panic::AssertUnwindSafe(f));
+	let elapsed = start.elapsed();
+
+	let res = res.map_err(|e| match e.downcast_ref::<Unsolved>() {
+		Some(_) => PartOutcome::Unsolved,
+		None => PartOutcome::Panicked(panic_message(&*e)),
+	});
+	(res, elapsed)
+}
+
+fn outcome<A: fmt::Display>(ans: Result<&A, &PartOutcome>) -> PartOutcome {
+	match ans {
+		Ok(ans) => PartOutcome::Solved(ans.to_string()),
+		Err(out) => out.clone(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::unsolved;
+
+	/// Sums the input's numbers for part 1, and doubles part 1's answer for part 2. `Some(n)` fails part 1 (panicking if n > 0).
+	#[derive(Debug, Clone, Copy)]
+	struct Chained(Option<u32>);
+
+	impl AoCDay for Chained {
+		type Data<'i> = Vec<u32>;
+		type Answer = u32;
+		const PART2_USES_PART1: bool = true;
+
+		fn day(&self) -> u8 {
+			1
+		}
+		fn parse(&self, input: &str) -> Vec<u32> {
+			input.split(',').map(|n| n.parse().unwrap()).collect()
+		}
+		fn part1(&self, data: &mut Vec<u32>) -> u32 {
+			match self.0 {
+				Some(0) => unsolved(),
+				Some(n) => panic!("bad input {}", n),
+				None => data.iter().sum(),
+			}
+		}
+		fn part2_with(&self, _data: &mut Vec<u32>, part1: u32) -> u32 {
+			part1 * 2
+		}
+	}
+
+	fn run(day: Chained, part: Option<DayPart>) -> RunReport {
+		DynDay::run(&day, part, "1,2,3")
+	}
+
+	#[test]
+	fn part2_receives_part1() {
+		let report = run(Chained(None), None);
+		let outcomes: Vec<&PartOutcome> = report.parts.iter().map(|p| &p.outcome).collect();
+		assert_eq!(outcomes, [&PartOutcome::Solved("6".to_string()), &PartOutcome::Solved("12".to_string())]);
+		// part 1 was requested itself, so isn't charged to part 2
+		assert_eq!(report.part(DayPart::Part2).unwrap().part1_elapsed, None);
+	}
+
+	#[test]
+	fn part1_run_on_part2s_behalf() {
+		let report = run(Chained(None), Some(DayPart::Part2));
+		assert!(report.part(DayPart::Part1).is_none());
+
+		let part2 = report.part(DayPart::Part2).unwrap();
+		assert_eq!(part2.outcome, PartOutcome::Solved("12".to_string()));
+		assert!(part2.part1_elapsed.is_some());
+		assert!(report.to_string().contains(" for part 1)"), "{}", report);
+	}
+
+	#[test]
+	fn part1_failures_passed_to_part2() {
+		let report = run(Chained(Some(3)), Some(DayPart::Part2));
+		assert_eq!(report.parts[0].outcome, PartOutcome::Panicked("part 1 panicked: bad input 3".to_string()));
+
+		let report = run(Chained(Some(0)), None);
+		assert_eq!(report.parts[0].outcome, PartOutcome::Unsolved);
+		assert_eq!(report.parts[1].outcome, PartOutcome::Unsolved);
+	}
+
+	#[test]
+	fn parse_panics_skip_parts() {
+		let report = DynDay::run(&Chained(None), None, "1,x");
+		assert!(report.parse_panic.is_some());
+		assert!(report.parts.is_empty());
+	}
+
+	#[test]
+	fn boxed_days() {
+		let days: Vec<Box<dyn DynDay>> = vec![Box::new(Chained(None)), Box::new(Chained(Some(0)))];
+		let reports: Vec<RunReport> = days.iter().map(|d| d.run(Some(DayPart::Part1), "4,5")).collect();
+		assert_eq!(reports[0].parts[0].outcome.answer(), Some("9"));
+		assert_eq!(reports[1].to_string(), "Day 1 Part 1: not solved yet\n");
+		assert_eq!(days[1].name(), "Chained(Some(0))");
+	}
+
+	#[test]
+	fn panics_disagree_with_answers() {
+		let report = |outcomes: &[PartOutcome]| RunReport {
+			day: 1,
+			parse_elapsed: Duration::ZERO,
+			parse_panic: None,
+			parts: outcomes.iter().map(|outcome| PartReport {
+				part: DayPart::Part1,
+				outcome: outcome.clone(),
+				elapsed: Duration::ZERO,
+				part1_elapsed: None,
+			}).collect(),
+		};
+		let solved = |ans: &str| report(&[PartOutcome::Solved(ans.to_string())]);
+		let panicked = report(&[PartOutcome::Panicked("oops".to_string())]);
+		let unsolved = report(&[PartOutcome::Unsolved]);
+		let agrees = |reports: &[RunReport]| VariantComparison { names: vec!["a".to_string(); reports.len()], reports }.agrees(DayPart::Part1);
+
+		assert!(agrees(&[solved("1"), solved("1"), unsolved.clone()]));
+		assert!(! agrees(&[solved("1"), solved("2")]));
+		assert!(! agrees(&[solved("1"), panicked.clone()]));
+		assert!(! agrees(&[panicked.clone(), solved("1")]));
+		assert!(agrees(&[panicked.clone(), panicked.clone()]));
+
+		let parse_panicked = RunReport { parse_panic: Some("bad input".to_string()), parts: Vec::new(), ..solved("1") };
+		assert!(! agrees(&[solved("1"), parse_panicked]));
+	}
+}
@@ -1,21 +1,52 @@
-#![feature(min_specialization)]
-
 use std::borrow::Cow;
 use std::ffi::OsString;
+use std::time::{Duration, Instant};
 
 #[cfg(feature = "alloclog")]
 pub mod alloclog;
 pub mod tree_node; // independent helper data structure
 
 pub mod aocday;
+pub mod dynday;
+pub mod params;
 //#[macro_use]
 //pub mod error_handling;
 pub mod testing;
 
-pub use aocday::{AoCDay, DayPart};
-pub use testing::{run_test, test_runner};
+pub use aocday::{AoCDay, AnswerTypes, Answers, Answer1, Answer2, DayPart, Part, Part1, Part2, Unsolved, unsolved};
+pub use dynday::{DynDay, RunReport, PartReport, PartOutcome, VariantComparison, VariantsDisagree};
+pub use params::Params;
+pub use testing::{run_snapshot, run_test, run_test_with, test_examples, test_part, test_part_with, test_runner, AllocBudget, Case, TestCase, TestConfig, TestInput, Variants};
+
+pub use aoch_proc::{aoc_inputs, examples, load_days, parse, part1, part2, scan, Parse};
+
+/// Runs `f` as a named phase (ie: parsing, or a part), to be included in the allocation summary under the `alloclog` feature
+#[cfg(feature = "alloclog")]
+pub(crate) fn alloc_phase<R>(name: &str, f: impl FnOnce() -> R) -> R {
+	alloclog::phase(name, f)
+}
+#[cfg(not(feature = "alloclog"))]
+pub(crate) fn alloc_phase<R>(_name: &str, f: impl FnOnce() -> R) -> R {
+	f()
+}
+
+/// Support for `#[aoch::parse]`, `#[aoch::part1]` and `#[aoch::part2]`
+#[doc(hidden)]
+pub mod __private {
+	use crate::{AoCDay, Answer1};
 
-pub use aoch_proc::{aoc_inputs, load_days};
+	pub trait Part1Fn: AoCDay {
+		type Output;
+		fn part1(data: &mut Self::Data<'_>) -> Self::Output;
+	}
+
+	pub trait Part2Fn: AoCDay {
+		type Output;
+		const USES_PART1: bool;
+		fn part2(data: &mut Self::Data<'_>) -> Self::Output;
+		fn part2_with(data: &mut Self::Data<'_>, part1: Answer1<Self>) -> Self::Output;
+	}
+}
 pub mod macros {
 	#[macro_export]
 	macro_rules! daystr {
@@ -24,6 +55,14 @@ pub mod macros {
 		};
 	}
 
+	/// Builds [`Params`](crate::Params) for a test case, ie: `params!(size = 7, steps = 12)`
+	#[macro_export]
+	macro_rules! params {
+		($($name: ident = $value: expr),* $(,)?) => {
+			$crate::Params::new()$(.with(stringify!($name), $value))*
+		};
+	}
+
 	#[macro_export]
 	macro_rules! aoc_input {
 		($daynum: literal) => {
@@ -56,6 +95,10 @@ struct DayRunCmd {
 
 	#[arg(short, long, default_value_t = false)]
 	two: bool,
+
+	/// Overrides a puzzle parameter, as `name=value`. May be repeated.
+	#[arg(short = 'P', long = "param", value_parser = Params::parse_pair)]
+	params: Vec<(String, String)>,
 }
 
 pub fn run_day<D: AoCDay>(day: D, inputstr: &str, part: Option<DayPart>) {
@@ -82,45 +125,91 @@ pub fn run_day<D: AoCDay>(day: D, inputstr: &str, part: Option<DayPart>) {
 		},
 	};
 
-	let mut parsed_input = (!args.parse_per_run).then(|| day.parse(&inp));
+	let params: Params = args.params.into_iter().collect();
+	let mut parsed_input = None;
+
+	#[cfg(feature = "alloclog")] alloclog::install();
 
 	for _ in 0..args.repeat {
-		if args.parse_per_run {
-			parsed_input = Some(day.parse(&inp));
+		let mut parse_elapsed = Duration::ZERO;
+		if args.parse_per_run || parsed_input.is_none() {
+			let start = Instant::now();
+			parsed_input = Some(alloc_phase("parse", || day.parse_with(&inp, &params)));
+			parse_elapsed = start.elapsed();
 		}
-		let mut data = parsed_input.as_mut().unwrap();
+		let data = parsed_input.as_mut().unwrap();
 		let exclusive = args.one ^ args.two;
 		let one = !exclusive || args.one || part == Some(DayPart::Part1);
 		let two = !exclusive || args.two || part == Some(DayPart::Part2);
-		if one {
-			let r = day.part1(&mut data);
-			if ! args.quiet {
-				println!("Day {} Part 1: {}", day.day(), r);
-			}
-		}
-		if two {
-			let r = day.part2(&mut data);
-			if ! args.quiet {
-				println!("Day {} Part 2: {}", day.day(), r);
-			}
+
+		let report = RunReport {
+			day: day.day(),
+			parse_elapsed,
+			parse_panic: None,
+			parts: dynday::run_parts(day, data, one, two),
+		};
+		if ! args.quiet {
+			print!("{}", report);
 		}
 	}
 
+	#[cfg(feature = "alloclog")] eprintln!("{}", alloclog::summary("allocations"));
+
 	// run_day_with_input(day, part, &inp, false);
 }
 
-pub fn run_day_with_input<D: AoCDay>(day: D, part: Option<DayPart>, inputstr: &str, quiet: bool) {
-	let mut data = day.parse(inputstr);
-	if matches!(part, None | Some(DayPart::Part1)) {
-		let p1_out = day.part1(&mut data);
+/// Parses the input once, and runs the requested part(s) against it, printing the answers unless `quiet`.
+pub fn run_day_with_input<D: DynDay + ?Sized>(day: &D, part: Option<DayPart>, inputstr: &str, quiet: bool) -> RunReport {
+	#[cfg(feature = "alloclog")] alloclog::install();
+	let report = day.run(part, inputstr);
+	if ! quiet {
+		print!("{}", report);
+	}
+	#[cfg(feature = "alloclog")] {
+		let allocs = alloclog::summary("allocations");
 		if ! quiet {
-			println!("Day {} Part 1: {}", day.day(), p1_out);
+			eprintln!("{}", allocs);
 		}
 	}
-	if matches!(part, None | Some(DayPart::Part2)) {
-		let p1_out = day.part2(&mut data);
-		if ! quiet {
-			println!("Day {} Part 2: {}", day.day(), p1_out);
+	report
+}
+
+/// Runs several variants of the same day against the same input, checking that their answers agree.
+///
+/// Unless `quiet`, prints each part's answer followed by every variant's timing. Returns each variant's report, in order, or an error
+/// holding them if the variants disagree on any part, including one panicking where another solves it. (so that `main` can return it, to exit with a failure)
+pub fn run_variants(variants: &[&dyn DynDay], part: Option<DayPart>, inputstr: &str, quiet: bool) -> Result<Vec<RunReport>, VariantsDisagree> {
+	#[cfg(feature = "alloclog")] alloclog::install();
+	#[cfg(feature = "alloclog")] let mut allocs = Vec::new();
+	let reports: Vec<RunReport> = variants.iter()
+		.map(|day| {
+			let report = day.run(part, inputstr);
+			#[cfg(feature = "alloclog")] allocs.push(alloclog::summary(&format!("allocations of {}", day.name())));
+			report
+		})
+		.collect();
+
+	let comparison = VariantComparison {
+		names: variants.iter().map(|day| day.name()).collect(),
+		reports: &reports,
+	};
+	let disagreements = comparison.disagreements();
+	if ! quiet {
+		match &reports[..] {
+			[report] => print!("{}", report),
+			_ => print!("{}", comparison),
 		}
+		#[cfg(feature = "alloclog")] for summary in allocs {
+			eprintln!("{}", summary);
+		}
+	}
+
+	match disagreements.is_empty() {
+		true => Ok(reports),
+		false => Err(VariantsDisagree {
+			day: reports[0].day,
+			parts: disagreements,
+			reports,
+		}),
 	}
 }
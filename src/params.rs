@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::iter::FromIterator;
+use std::str::FromStr;
+
+/// Named puzzle parameters, for constants that differ between the examples and the real input. (ie: a grid size of 7 vs 71, or "after 10 steps" vs "after 1000")
+///
+/// Days read them in [`crate::AoCDay::parse_with`], falling back to the real input's value for any that are unset.
+/// Test cases can provide them as `(input, params, expected)`, and `run_day` accepts overrides as `--param name=value`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Params {
+	values: BTreeMap<String, String>,
+}
+
+impl Params {
+	pub const fn new() -> Params {
+		Params { values: BTreeMap::new() }
+	}
+
+	/// Sets a parameter, builder style. See also the [`crate::params!`] macro.
+	pub fn with<V: ToString>(mut self, name: &str, value: V) -> Params {
+		self.set(name, value);
+		self
+	}
+
+	/// Sets a parameter, replacing any previous value.
+	pub fn set<V: ToString>(&mut self, name: &str, value: V) {
+		self.values.insert(name.to_string(), value.to_string());
+	}
+
+	/// The raw value of a parameter, if set.
+	pub fn get_str(&self, name: &str) -> Option<&str> {
+		self.values.get(name).map(String::as_str)
+	}
+
+	/// Parses the value of a parameter, or returns `default` (the real input's value) if unset.
+	///
+	/// Panics if the parameter is set, but cannot be parsed as a `T`.
+	pub fn get<T>(&self, name: &str, default: T) -> T
+	where
+		T: FromStr,
+		T::Err: fmt::Debug,
+	{
+		match self.get_str(name) {
+			None => default,
+			Some(value) => value.parse()
+				.unwrap_or_else(|e| panic!("invalid value for parameter `{}` ({:?}): {:?}", name, value, e)),
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.values.is_empty()
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+		self.values.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+	}
+
+	/// Parses a single `name=value` pair, as given on the command line.
+	pub fn parse_pair(pair: &str) -> Result<(String, String), String> {
+		match pair.split_once('=') {
+			Some((name, value)) if ! name.trim().is_empty() => Ok((name.trim().to_string(), value.trim().to_string())),
+			_ => Err(format!("expected a parameter as `name=value`, got {:?}", pair)),
+		}
+	}
+}
+
+impl<S: Into<String>> FromIterator<(S, S)> for Params {
+	fn from_iter<T: IntoIterator<Item = (S, S)>>(iter: T) -> Params {
+		Params {
+			values: iter.into_iter().map(|(k, v)| (k.into(), v.into())).collect(),
+		}
+	}
+}
+
+/// Formatted as `name=value, name=value`
+impl fmt::Display for Params {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		for (i, (name, value)) in self.iter().enumerate() {
+			if i > 0 {
+				write!(f, ", ")?;
+			}
+			write!(f, "{}={}", name, value)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn defaults_and_overrides() {
+		let params = crate::params!(size = 7, steps = "12");
+		assert_eq!(params.get("size", 71), 7);
+		assert_eq!(params.get::<u64>("steps", 1000), 12);
+		assert_eq!(params.get("missing", 3), 3);
+		assert_eq!(params.get_str("missing"), None);
+		assert_eq!(params.to_string(), "size=7, steps=12");
+
+		let params: Params = vec![("size", "9")].into_iter().collect();
+		assert_eq!(params.get("size", 71), 9);
+		assert!(Params::new().is_empty());
+	}
+
+	#[test]
+	#[should_panic(expected = "invalid value for parameter `size` (\"big\")")]
+	fn unparsable_values_panic() {
+		Params::new().with("size", "big").get("size", 71);
+	}
+
+	#[test]
+	fn command_line_pairs() {
+		assert_eq!(Params::parse_pair(" size = 7"), Ok(("size".to_string(), "7".to_string())));
+		assert_eq!(Params::parse_pair("expr=a=b"), Ok(("expr".to_string(), "a=b".to_string())));
+		assert!(Params::parse_pair("size").is_err());
+		assert!(Params::parse_pair("=7").is_err());
+	}
+}
@@ -1,5 +1,32 @@
-use std::str::FromStr;
 use std::fmt::Debug;
+use std::str::FromStr;
+
+mod error;
+mod grid;
+mod ints;
+mod scan;
+
+pub use error::ParseError;
+pub use grid::{Grid, Point};
+pub use ints::{first_ints, first_ints_per_line, ints, ints_per_line, Integer, Ints};
+pub use scan::Scanner;
+
+/// Parses a value from a line, possibly borrowing from it (ie: a struct with `&'i str` fields, from `#[derive(aoch::Parse)]`).
+///
+/// Implemented for every [`FromStr`] type, so that the helpers below take either. Their errors need only implement `Debug` (and be `Send + Sync`, to be kept within the [`ParseError`]).
+pub trait FromLine<'i>: Sized {
+    fn from_line(line: &'i str) -> Result<Self, ParseError>;
+}
+
+impl<'i, T> FromLine<'i> for T
+where
+    T: FromStr,
+    T::Err: Debug + Send + Sync + 'static,
+{
+    fn from_line(line: &'i str) -> Result<T, ParseError> {
+        line.parse().map_err(ParseError::from_debug)
+    }
+}
 
 /// Trims the string, and returns it if the length is greater than zero.
 pub fn trimmed<'a>(s: &'a str) -> Option<&'a str> {
@@ -11,18 +38,23 @@ pub fn trimmed<'a>(s: &'a str) -> Option<&'a str> {
     }
 }
 
-/// Takes each line with content, and passes them pre-trimmed to the mapping function
-pub fn try_from_lines_with<T, E: Debug, F: FnMut(&str) -> Result<T, E>>(input: &str, mut map: F) -> Vec<T> {
-    input
-        .lines()
-        .enumerate()
-        .filter_map(|(i, itm)| trimmed(itm).map(|itm| (i, itm)))
-        .map(|(i, itm)| map(itm).expect(&format!("error parsing input line {}", i)))
-        .collect::<Vec<T>>()
+/// Takes each line with content, and passes them pre-trimmed to the mapping function, stopping at the first error.
+///
+/// Errors are given the line they happened on, as a [`ParseError`].
+pub fn try_from_lines_with<'i, T, E, F>(input: &'i str, mut map: F) -> Result<Vec<T>, ParseError>
+where
+    E: Debug + Send + Sync + 'static,
+    F: FnMut(&'i str) -> Result<T, E>,
+{
+    numbered_lines(input)
+        .map(|(num, line)| map(line).map_err(|e| ParseError::from_debug(e).with_line(num, line)))
+        .collect()
 }
 
 /// Takes each line with content, and passes them pre-trimmed to the mapping function
-pub fn from_lines_with<T, F: FnMut(&str) -> T>(input: &str, map: F) -> Vec<T> {
+///
+/// Lines borrow from the input, so may be kept as-is in the results (ie: the `&str` captures of `aoch::scan!`)
+pub fn from_lines_with<'i, T, F: FnMut(&'i str) -> T>(input: &'i str, map: F) -> Vec<T> {
     input
         .lines()
         .filter_map(trimmed)
@@ -31,37 +63,95 @@ pub fn from_lines_with<T, F: FnMut(&str) -> T>(input: &str, map: F) -> Vec<T> {
 }
 
 
-/// Returns a vector of the specified type, parsed from strings using [`std::str::parse`].
+/// Returns a vector of the specified type, parsed from strings using [`FromLine`] (or [`std::str::parse`]).
 ///
 /// Trims whitespace and skips empty strings
-pub fn from_iter<'a, T: FromStr, I: Iterator<Item = &'a str>>(
-    iter: I,
-) -> Result<Vec<T>, T::Err> {
+pub fn from_iter<'i, T: FromLine<'i>, I: Iterator<Item = &'i str>>(iter: I) -> Result<Vec<T>, ParseError> {
     iter.filter_map(trimmed)
-        .map(str::parse)
-        .collect::<Result<Vec<T>, T::Err>>()
+        .map(|s| T::from_line(s).map_err(|e| e.with_text(s)))
+        .collect()
 }
 
-/// Returns a vector of the specified type, parsed from lines using [`std::str::parse`].
+/// Returns a vector of the specified type, parsed from lines using [`FromLine`] (or [`std::str::parse`]).
 ///
 /// Trims whitespace and skips empty lines
-pub fn from_lines<T: FromStr>(input: &str) -> Result<Vec<T>, T::Err> {
-    input
-        .lines()
-        .filter_map(trimmed)
-        .map(str::parse)
-        .collect::<Result<Vec<T>, T::Err>>()
+pub fn from_lines<'i, T: FromLine<'i>>(input: &'i str) -> Result<Vec<T>, ParseError> {
+    try_from_lines_with(input, T::from_line)
 }
 
-pub fn from_grouped_lines<T: FromStr>(input: &str) -> Result<Vec<Vec<T>>, T::Err> {
-    input
-        .split_terminator("\n\n")
-        .filter_map(trimmed)
-        .map(|s| {
-            s.split_terminator("\n")
-                .filter_map(trimmed)
-                .map(str::parse)
-                .collect::<Result<Vec<T>, T::Err>>()
-        })
-        .collect::<Result<Vec<Vec<T>>, T::Err>>()
+/// Returns a vector of each group of lines, as separated by blank lines, parsed using [`FromLine`] (or [`std::str::parse`]).
+///
+/// Errors are given the group they happened in (starting at 1), as well as the line.
+pub fn from_grouped_lines<'i, T: FromLine<'i>>(input: &'i str) -> Result<Vec<Vec<T>>, ParseError> {
+    let mut groups = Vec::new();
+    let mut group = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        match trimmed(line) {
+            None if group.is_empty() => {},
+            None => groups.push(std::mem::take(&mut group)),
+            Some(line) => {
+                let parsed = T::from_line(line).map_err(|e| e.with_line(i + 1, line).with_group(groups.len() + 1))?;
+                group.push(parsed);
+            },
+        }
+    }
+    if ! group.is_empty() {
+        groups.push(group);
+    }
+    Ok(groups)
+}
+
+/// Each line with content, pre-trimmed, with its line number (starting at 1)
+fn numbered_lines(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    input.lines()
+        .enumerate()
+        .filter_map(|(i, line)| trimmed(line).map(|line| (i + 1, line)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A type whose error only implements `Debug`
+    #[derive(Debug, PartialEq)]
+    struct Digit(u8);
+
+    impl FromStr for Digit {
+        type Err = ();
+        fn from_str(s: &str) -> Result<Digit, ()> {
+            match s.as_bytes() {
+                [d @ b'0'..=b'9'] => Ok(Digit(d - b'0')),
+                _ => Err(()),
+            }
+        }
+    }
+
+    #[test]
+    fn lines() {
+        assert_eq!(from_lines::<i32>("\n 1\n\n-2 \n").unwrap(), [1, -2]);
+        assert_eq!(from_lines::<Digit>("3\n4").unwrap(), [Digit(3), Digit(4)]);
+
+        let err = from_lines::<i32>("1\n\nx").unwrap_err();
+        assert_eq!((err.line, err.text.as_str()), (Some(3), "x"));
+        assert_eq!(err.error().to_string(), "invalid digit found in string");
+        let err = from_lines::<Digit>("1\n23").unwrap_err();
+        assert_eq!((err.line, err.error().to_string()), (Some(2), "()".to_string()));
+    }
+
+    #[test]
+    fn lines_with() {
+        let parsed = try_from_lines_with("a1\nb2", |line| line[1..].parse::<u8>().map_err(|_| ()));
+        assert_eq!(parsed.unwrap(), [1, 2]);
+        let err = try_from_lines_with("a1\nb", |line| line.strip_prefix('a').ok_or("expected an a")).unwrap_err();
+        assert_eq!(err.to_string(), "line 2: expected an a\n  |\n2 | b\n  | ^");
+    }
+
+    #[test]
+    fn grouped_lines() {
+        let groups: Vec<Vec<u32>> = from_grouped_lines("\n1\n2\n\n\n3\n").unwrap();
+        assert_eq!(groups, [vec![1, 2], vec![3]]);
+
+        let err = from_grouped_lines::<u32>("1\n\n2\nx").unwrap_err();
+        assert_eq!((err.group, err.line), (Some(2), Some(4)));
+    }
 }
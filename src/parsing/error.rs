@@ -0,0 +1,203 @@
+//! Parse errors that show where in the input they happened
+
+use std::any::Any;
+use std::char::ParseCharError;
+use std::error::Error;
+use std::fmt;
+use std::num::{ParseFloatError, ParseIntError};
+use std::str::ParseBoolError;
+
+/// An error parsing the input, with the line it happened on.
+///
+/// Written with a caret under the offending text:
+///
+/// ```text
+/// line 3, column 3: invalid digit found in string
+///   |
+/// 3 | 1-x a: abcde
+///   |   ^
+/// ```
+pub struct ParseError {
+    /// The group of lines the error is in, for [`from_grouped_lines`](super::from_grouped_lines) (starting at 1)
+    pub group: Option<usize>,
+    /// The line of the input the error is on (starting at 1)
+    pub line: Option<usize>,
+    /// The character within the line where the error is (starting at 1), if known
+    pub column: Option<usize>,
+    /// The offending line
+    pub text: String,
+    error: Box<dyn Error + Send + Sync>,
+}
+
+impl ParseError {
+    /// An error without any location yet. Errors that are already a `ParseError` (ie: from `aoch::scan!`) are kept as they are.
+    pub fn new<E: Into<Box<dyn Error + Send + Sync>>>(error: E) -> ParseError {
+        match error.into().downcast::<ParseError>() {
+            Ok(err) => *err,
+            Err(error) => ParseError { group: None, line: None, column: None, text: String::new(), error },
+        }
+    }
+
+    /// An error from a parser whose error type need only implement `Debug` (ie: a `FromStr` type with `Err = ()`).
+    ///
+    /// `ParseError`s are kept as they are. The standard library's parse errors are kept as the underlying error, so that
+    /// [`ParseError::error`] can be downcast to them, and string messages are written as-is. Anything else is written with `Debug`.
+    pub(crate) fn from_debug<E: fmt::Debug + Send + Sync + 'static>(error: E) -> ParseError {
+        type AnyBox = Box<dyn Any + Send + Sync>;
+        fn boxed<T: Error + Send + Sync + 'static>(any: AnyBox) -> Result<Box<dyn Error + Send + Sync>, AnyBox> {
+            any.downcast::<T>().map(|error| error as Box<dyn Error + Send + Sync>)
+        }
+
+        let any: AnyBox = Box::new(error);
+        let any = match any.downcast::<ParseError>() {
+            Ok(parse) => return *parse,
+            Err(any) => any,
+        };
+        let error = boxed::<ParseIntError>(any)
+            .or_else(boxed::<ParseFloatError>)
+            .or_else(boxed::<ParseBoolError>)
+            .or_else(boxed::<ParseCharError>)
+            .or_else(|any| any.downcast::<Box<dyn Error + Send + Sync>>().map(|error| *error))
+            .or_else(|any| any.downcast::<String>().map(|msg| (*msg).into()))
+            .or_else(|any| any.downcast::<&str>().map(|msg| (*msg).into()))
+            .unwrap_or_else(|any| Box::new(DebugError(*any.downcast::<E>().unwrap())));
+        ParseError::new(error)
+    }
+
+    /// Sets the line the error is on, unless already known
+    pub fn with_line(mut self, line: usize, text: &str) -> ParseError {
+        if self.line.is_none() {
+            self.line = Some(line);
+        }
+        self.with_text(text)
+    }
+
+    /// Sets the offending text, unless already known
+    pub fn with_text(mut self, text: &str) -> ParseError {
+        if self.text.is_empty() {
+            self.text = text.to_string();
+        }
+        self
+    }
+
+    /// Sets the character within the line where the error is
+    pub fn with_column(mut self, column: usize) -> ParseError {
+        self.column = Some(column);
+        self
+    }
+
+    /// Sets the group of lines the error is in
+    pub fn with_group(mut self, group: usize) -> ParseError {
+        self.group = Some(group);
+        self
+    }
+
+    /// The underlying error
+    pub fn error(&self) -> &(dyn Error + Send + Sync + 'static) {
+        &*self.error
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let location: Vec<String> = [("group", self.group), ("line", self.line), ("column", self.column)].iter()
+            .filter_map(|(name, n)| n.map(|n| format!("{} {}", name, n)))
+            .collect();
+        match location.is_empty() {
+            true => write!(f, "{}", self.error)?,
+            false => write!(f, "{}: {}", location.join(", "), self.error)?,
+        }
+        if self.text.is_empty() {
+            return Ok(());
+        }
+
+        let num = self.line.map(|line| line.to_string()).unwrap_or_default();
+        let gutter = " ".repeat(num.len());
+        let caret = match self.column {
+            Some(column) => format!("{}^", " ".repeat(column.saturating_sub(1))),
+            None => "^".repeat(self.text.chars().count().max(1)),
+        };
+        write!(f, "\n{} |\n{} | {}\n{} | {}", gutter, num, self.text, gutter, caret)
+    }
+}
+
+/// The same as `Display`, so that unwrapping a parse result shows where it failed
+impl fmt::Debug for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&*self.error)
+    }
+}
+
+/// Keeps an error that only implements `Debug` as a [`ParseError`]'s underlying error, written with its `Debug` output
+struct DebugError<E>(E);
+
+impl<E: fmt::Debug> fmt::Display for DebugError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+impl<E: fmt::Debug> fmt::Debug for DebugError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<E: fmt::Debug> Error for DebugError<E> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caret() {
+        let err = ParseError::new("invalid digit found in string").with_line(3, "1-x a: abcde").with_column(3);
+        assert_eq!(err.to_string(), "line 3, column 3: invalid digit found in string\n  |\n3 | 1-x a: abcde\n  |   ^");
+    }
+
+    #[test]
+    fn whole_line() {
+        let err = ParseError::new("unknown instruction").with_line(12, "jmp").with_group(2);
+        assert_eq!(err.to_string(), "group 2, line 12: unknown instruction\n   |\n12 | jmp\n   | ^^^");
+        // the first location found is kept, as errors pass up through nested parsers
+        let err = err.with_line(1, "other");
+        assert_eq!((err.line, err.text.as_str()), (Some(12), "jmp"));
+
+        assert_eq!(ParseError::new("no location").to_string(), "no location");
+    }
+
+    #[test]
+    fn keeps_parse_errors() {
+        let inner = ParseError::new("inner").with_text("a b").with_column(3);
+        let err = ParseError::new(Box::new(inner) as Box<dyn Error + Send + Sync>);
+        assert_eq!(err.column, Some(3));
+        let err = ParseError::from_debug(ParseError::new("inner").with_column(2));
+        assert_eq!(err.column, Some(2));
+    }
+
+    #[test]
+    fn from_debug() {
+        #[derive(Debug)]
+        struct Unknown;
+        let message = |err: ParseError| err.error().to_string();
+
+        assert_eq!(message(ParseError::from_debug("x".parse::<u8>().unwrap_err())), "invalid digit found in string");
+        assert_eq!(message(ParseError::from_debug("expected a digit".to_string())), "expected a digit");
+        assert_eq!(message(ParseError::from_debug("expected a digit")), "expected a digit");
+        assert_eq!(message(ParseError::from_debug(())), "()");
+        assert_eq!(message(ParseError::from_debug(Unknown)), "Unknown");
+
+        // the original error is kept, rather than only its message
+        let err = ParseError::from_debug("x".parse::<u8>().unwrap_err());
+        assert!(err.error().downcast_ref::<ParseIntError>().is_some());
+        assert!(err.source().is_some_and(|source| source.is::<ParseIntError>()));
+        let err = ParseError::from_debug("1.5".parse::<bool>().unwrap_err());
+        assert!(err.error().is::<ParseBoolError>());
+    }
+}
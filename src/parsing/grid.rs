@@ -0,0 +1,513 @@
+//! 2D grids of cells, as parsed from character maps
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::{Add, AddAssign, Index, IndexMut, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+use super::ParseError;
+
+/// A position within a [`Grid`], or an offset between two. `x` increases to the right, and `y` downwards.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl Point {
+    pub const ZERO: Point = Point::new(0, 0);
+    pub const UP: Point = Point::new(0, -1);
+    pub const DOWN: Point = Point::new(0, 1);
+    pub const LEFT: Point = Point::new(-1, 0);
+    pub const RIGHT: Point = Point::new(1, 0);
+
+    /// Up, right, down and left
+    pub const ORTHOGONAL: [Point; 4] = [Point::UP, Point::RIGHT, Point::DOWN, Point::LEFT];
+    /// Clockwise from up, including diagonals
+    pub const ADJACENT: [Point; 8] = [
+        Point::new(0, -1), Point::new(1, -1), Point::new(1, 0), Point::new(1, 1),
+        Point::new(0, 1), Point::new(-1, 1), Point::new(-1, 0), Point::new(-1, -1),
+    ];
+
+    pub const fn new(x: isize, y: isize) -> Point {
+        Point { x, y }
+    }
+
+    /// The sum of the absolute distances along each axis
+    pub fn manhattan(self, other: Point) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
+    /// Turned 90 degrees clockwise, as a direction (ie: `UP` to `RIGHT`)
+    pub fn turn_right(self) -> Point {
+        Point::new(-self.y, self.x)
+    }
+
+    /// Turned 90 degrees counter-clockwise, as a direction (ie: `UP` to `LEFT`)
+    pub fn turn_left(self) -> Point {
+        Point::new(self.y, -self.x)
+    }
+}
+
+impl From<(isize, isize)> for Point {
+    fn from((x, y): (isize, isize)) -> Point {
+        Point::new(x, y)
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+    fn add(self, rhs: Point) -> Point {
+        Point::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+    fn sub(self, rhs: Point) -> Point {
+        Point::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Mul<isize> for Point {
+    type Output = Point;
+    fn mul(self, rhs: isize) -> Point {
+        Point::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl Neg for Point {
+    type Output = Point;
+    fn neg(self) -> Point {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+impl AddAssign for Point {
+    fn add_assign(&mut self, rhs: Point) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Point {
+    fn sub_assign(&mut self, rhs: Point) {
+        *self = *self - rhs;
+    }
+}
+
+/// A rectangular grid of cells, stored row by row.
+///
+/// ```ignore
+/// type Data<'i> = Grid<u8>;
+///
+/// fn parse<'i>(&self, input: &'i str) -> Self::Data<'i> {
+///     Grid::try_parse_with(input, |c| c.to_digit(10).map(|d| d as u8).ok_or("not a digit")).unwrap()
+/// }
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// A `width` by `height` grid, with every cell set to `fill`
+    pub fn new(width: usize, height: usize, fill: T) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid { width, height, cells: vec![fill; width * height] }
+    }
+
+    /// A grid of `width` columns, from cells given row by row.
+    ///
+    /// Panics if the cells don't fill a whole number of rows.
+    #[allow(unknown_lints, clippy::manual_is_multiple_of)] // `usize::is_multiple_of` is too recent for older toolchains
+    pub fn from_vec(width: usize, cells: Vec<T>) -> Grid<T> {
+        assert!(width > 0 && cells.len() % width == 0, "{} cells do not fit into rows of {}", cells.len(), width);
+        Grid { width, height: cells.len() / width, cells }
+    }
+
+    /// Parses a grid with one cell per character, mapped by `map`.
+    ///
+    /// Blank lines before and after the grid are skipped, so that test inputs may start on their own line.
+    pub fn parse_with<F: FnMut(char) -> T>(input: &str, mut map: F) -> Result<Grid<T>, ParseError> {
+        Grid::try_parse_with(input, |c| Ok::<T, std::convert::Infallible>(map(c)))
+    }
+
+    /// Parses a grid with one cell per character, mapped by `map`, which may reject characters.
+    pub fn try_parse_with<E, F>(input: &str, mut map: F) -> Result<Grid<T>, ParseError>
+    where
+        E: fmt::Display,
+        F: FnMut(char) -> Result<T, E>,
+    {
+        let lines: Vec<&str> = input.lines().map(|line| line.trim_end_matches('\r')).collect();
+        let first = lines.iter().position(|line| ! line.trim().is_empty()).unwrap_or(lines.len());
+        let last = lines.iter().rposition(|line| ! line.trim().is_empty()).map_or(first, |last| last + 1);
+
+        let mut cells = Vec::with_capacity(input.len());
+        let mut width = None;
+        for (i, line) in lines.iter().enumerate().take(last).skip(first) {
+            let before = cells.len();
+            for (column, cell) in line.chars().enumerate() {
+                let mapped = map(cell).map_err(|e| {
+                    ParseError::new(format!("invalid cell {:?}: {}", cell, e)).with_line(i + 1, line).with_column(column + 1)
+                })?;
+                cells.push(mapped);
+            }
+            let len = cells.len() - before;
+            match width {
+                None => width = Some(len),
+                Some(width) if width != len => {
+                    let msg = format!("row is {} cells wide, expected {} like the first row", len, width);
+                    return Err(ParseError::new(msg).with_line(i + 1, line).with_column(width.min(len) + 1));
+                },
+                Some(_) => {},
+            }
+        }
+        let width = width.unwrap_or(0);
+        Ok(Grid { width, height: last - first, cells })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Whether `p` is within the grid
+    pub fn in_bounds(&self, p: Point) -> bool {
+        (0..self.width as isize).contains(&p.x) && (0..self.height as isize).contains(&p.y)
+    }
+
+    fn index_of(&self, p: Point) -> Option<usize> {
+        self.in_bounds(p).then(|| p.y as usize * self.width + p.x as usize)
+    }
+
+    fn point_of(&self, i: usize) -> Point {
+        Point::new((i % self.width) as isize, (i / self.width) as isize)
+    }
+
+    /// The cell at `p`, if within the grid
+    pub fn get(&self, p: Point) -> Option<&T> {
+        self.index_of(p).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, p: Point) -> Option<&mut T> {
+        self.index_of(p).map(move |i| &mut self.cells[i])
+    }
+
+    /// Every position in the grid, row by row
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width as isize;
+        (0..self.height as isize).flat_map(move |y| (0..width).map(move |x| Point::new(x, y)))
+    }
+
+    /// Every cell with its position, row by row
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.points().zip(self.cells.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Point, &mut T)> {
+        self.points().zip(self.cells.iter_mut())
+    }
+
+    /// The neighbours of `p` above, right, below and left of it, that are within the grid
+    pub fn neighbours4(&self, p: Point) -> impl Iterator<Item = Point> + '_ {
+        Point::ORTHOGONAL.iter().map(move |&d| p + d).filter(move |&n| self.in_bounds(n))
+    }
+
+    /// The neighbours of `p` including diagonals, that are within the grid
+    pub fn neighbours8(&self, p: Point) -> impl Iterator<Item = Point> + '_ {
+        Point::ADJACENT.iter().map(move |&d| p + d).filter(move |&n| self.in_bounds(n))
+    }
+
+    /// The positions from `start` stepping by `step` until leaving the grid, starting with `start` itself if within it
+    pub fn ray(&self, start: Point, step: Point) -> impl Iterator<Item = Point> + '_ {
+        std::iter::successors(Some(start), move |&p| Some(p + step)).take_while(move |&p| self.in_bounds(p))
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // `chunks` panics on zero sized chunks, so empty grids have no rows
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        assert!(x < self.width, "column {} is outside of the {}x{} grid", x, self.width, self.height);
+        self.cells.iter().skip(x).step_by(self.width.max(1)).take(self.height)
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(move |x| self.column(x))
+    }
+
+    /// Each diagonal running down and to the right, starting from the bottom left corner
+    pub fn diagonals(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        let (width, height) = (self.width as isize, self.height as isize);
+        // starting on the left edge, from the bottom up, then the top edge
+        let starts = (0..height).rev().map(|y| Point::new(0, y))
+            .chain((1..width).map(|x| Point::new(x, 0)));
+        starts.map(move |start| self.ray(start, Point::new(1, 1)).map(move |p| &self[p]))
+    }
+
+    /// Each diagonal running down and to the left, starting from the top left corner
+    pub fn anti_diagonals(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        let (width, height) = (self.width as isize, self.height as isize);
+        // starting on the top edge, from the left, then the right edge
+        let starts = (0..width).map(|x| Point::new(x, 0))
+            .chain((1..height).map(move |y| Point::new(width - 1, y)));
+        starts.map(move |start| self.ray(start, Point::new(-1, 1)).map(move |p| &self[p]))
+    }
+
+    /// The position of the first cell equal to `value`, row by row
+    pub fn find(&self, value: &T) -> Option<Point>
+    where
+        T: PartialEq,
+    {
+        self.cells.iter().position(|cell| cell == value).map(|i| self.point_of(i))
+    }
+
+    /// The positions of every cell equal to `value`, row by row
+    pub fn positions<'a>(&'a self, value: &'a T) -> impl Iterator<Item = Point> + 'a
+    where
+        T: PartialEq,
+    {
+        self.iter().filter(move |(_, cell)| *cell == value).map(|(p, _)| p)
+    }
+
+    /// A grid of the same size, with each cell mapped by `f`
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U> {
+        Grid { width: self.width, height: self.height, cells: self.cells.iter().map(f).collect() }
+    }
+
+    /// A `width` by `height` grid, filled from the cells of this one at the positions given by `source`
+    fn remap(&self, width: usize, height: usize, source: impl Fn(usize, usize) -> (usize, usize)) -> Grid<T>
+    where
+        T: Clone,
+    {
+        let cells = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let (sx, sy) = source(x, y);
+                self.cells[sy * self.width + sx].clone()
+            })
+            .collect();
+        Grid { width, height, cells }
+    }
+
+    /// Swaps rows and columns
+    pub fn transpose(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        self.remap(self.height, self.width, |x, y| (y, x))
+    }
+
+    /// Rotated 90 degrees clockwise
+    pub fn rotate_right(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        let height = self.height;
+        self.remap(self.height, self.width, move |x, y| (y, height - 1 - x))
+    }
+
+    /// Rotated 90 degrees counter-clockwise
+    pub fn rotate_left(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        let width = self.width;
+        self.remap(self.height, self.width, move |x, y| (width - 1 - y, x))
+    }
+
+    /// Mirrored left to right
+    pub fn flip_horizontal(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        let width = self.width;
+        self.remap(self.width, self.height, move |x, y| (width - 1 - x, y))
+    }
+
+    /// Mirrored top to bottom
+    pub fn flip_vertical(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        let height = self.height;
+        self.remap(self.width, self.height, move |x, y| (x, height - 1 - y))
+    }
+}
+
+impl FromStr for Grid<char> {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Grid<char>, ParseError> {
+        Grid::parse_with(s, |c| c)
+    }
+}
+
+impl FromStr for Grid<u8> {
+    type Err = ParseError;
+    /// Each character as its byte, for ASCII grids
+    fn from_str(s: &str) -> Result<Grid<u8>, ParseError> {
+        Grid::try_parse_with(s, |c| u8::try_from(c).map_err(|_| "not an ASCII character"))
+    }
+}
+
+impl Grid<u8> {
+    /// Displays each cell as its ASCII character, so that a grid parsed as bytes is written back as it was parsed
+    pub fn display_ascii(&self) -> impl fmt::Display + '_ {
+        DisplayAscii(self)
+    }
+}
+
+struct DisplayAscii<'g>(&'g Grid<u8>);
+
+impl fmt::Display for DisplayAscii<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.0.rows() {
+            for &cell in row {
+                write!(f, "{}", cell as char)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+    fn index(&self, p: Point) -> &T {
+        match self.index_of(p) {
+            Some(i) => &self.cells[i],
+            None => panic!("{} is outside of the {}x{} grid", p, self.width, self.height),
+        }
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, p: Point) -> &mut T {
+        match self.index_of(p) {
+            Some(i) => &mut self.cells[i],
+            None => panic!("{} is outside of the {}x{} grid", p, self.width, self.height),
+        }
+    }
+}
+
+/// Each row on its own line, so that a `Grid<char>` is written back as it was parsed. (`Grid<u8>` cells are written as numbers,
+/// see [`Grid::display_ascii`])
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Written row by row, rather than as a single list of every cell
+impl<T: fmt::Debug> fmt::Debug for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Grid {}x{} [", self.width, self.height)?;
+        for row in self.rows() {
+            writeln!(f, "    {:?}", row)?;
+        }
+        write!(f, "]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(s: &str) -> Grid<char> {
+        s.parse().unwrap()
+    }
+
+    fn rows<'a, I: Iterator<Item = &'a char>>(lines: impl Iterator<Item = I>) -> Vec<String> {
+        lines.map(|line| line.collect()).collect()
+    }
+
+    #[test]
+    fn parses() {
+        let g = grid("\n\nab\r\ncd\nef\n\n");
+        assert_eq!((g.width(), g.height()), (2, 3));
+        assert_eq!(g[Point::new(1, 2)], 'f');
+        assert_eq!(g.get(Point::new(2, 0)), None);
+        assert_eq!(g.to_string(), "ab\ncd\nef\n");
+
+        let err = "ab\nc\n".parse::<Grid<char>>().unwrap_err();
+        assert!(err.to_string().contains("row is 1 cells wide, expected 2"), "{}", err);
+        let err = Grid::try_parse_with("12\n3x", |c| c.to_digit(10).ok_or("not a digit")).unwrap_err();
+        assert!(err.to_string().contains("invalid cell 'x'"), "{}", err);
+    }
+
+    #[test]
+    fn ascii() {
+        let g: Grid<u8> = "#.\n.#".parse().unwrap();
+        assert_eq!(g[Point::new(0, 0)], b'#');
+        assert_eq!(g.display_ascii().to_string(), "#.\n.#\n");
+        assert_eq!(g.to_string(), "3546\n4635\n");
+    }
+
+    #[test]
+    fn rotations() {
+        let g = grid("abc\ndef");
+        assert_eq!(g.transpose().to_string(), "ad\nbe\ncf\n");
+        assert_eq!(g.rotate_right().to_string(), "da\neb\nfc\n");
+        assert_eq!(g.rotate_left().to_string(), "cf\nbe\nad\n");
+        assert_eq!(g.flip_horizontal().to_string(), "cba\nfed\n");
+        assert_eq!(g.flip_vertical().to_string(), "def\nabc\n");
+        assert_eq!(g.rotate_right().rotate_left(), g);
+        assert_eq!(g.rotate_right().rotate_right(), g.flip_horizontal().flip_vertical());
+    }
+
+    #[test]
+    fn lines() {
+        let g = grid("abc\ndef");
+        assert_eq!(g.row(1), ['d', 'e', 'f']);
+        assert_eq!(rows(g.columns()), ["ad", "be", "cf"]);
+        assert_eq!(rows(g.diagonals()), ["d", "ae", "bf", "c"]);
+        assert_eq!(rows(g.anti_diagonals()), ["a", "bd", "ce", "f"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "column 3 is outside of the 3x2 grid")]
+    fn column_out_of_bounds() {
+        let _ = grid("abc\ndef").column(3);
+    }
+
+    #[test]
+    #[should_panic(expected = "5 cells do not fit into rows of 2")]
+    fn from_vec_partial_row() {
+        Grid::from_vec(2, vec![0; 5]);
+    }
+
+    #[test]
+    fn neighbours() {
+        let g = Grid::new(3, 3, 0);
+        assert_eq!(g.neighbours4(Point::ZERO).collect::<Vec<_>>(), [Point::RIGHT, Point::DOWN]);
+        assert_eq!(g.neighbours8(Point::new(1, 1)).count(), 8);
+        assert_eq!(g.ray(Point::ZERO, Point::new(1, 1)).count(), 3);
+        assert_eq!(Point::UP.turn_right(), Point::RIGHT);
+        assert_eq!(Point::UP.turn_left(), Point::LEFT);
+        assert_eq!(Point::new(1, -2).manhattan(Point::new(-1, 1)), 5);
+    }
+}
@@ -0,0 +1,188 @@
+//! Extracting the integers from lines of mostly text, ie: `Sensor at x=2, y=18: closest beacon is at x=-2, y=15`
+
+use std::any::type_name;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use super::ParseError;
+
+/// An integer type that can be extracted with [`ints`]. Negative numbers do not fit in unsigned types.
+pub trait Integer: FromStr + Copy {
+    const SIGNED: bool;
+}
+
+macro_rules! impl_integer {
+    ($signed: expr; $($t: ty),*) => {
+        $(impl Integer for $t {
+            const SIGNED: bool = $signed;
+        })*
+    };
+}
+impl_integer!(true; i8, i16, i32, i64, i128, isize);
+impl_integer!(false; u8, u16, u32, u64, u128, usize);
+
+/// An iterator over the integers within a string, from [`ints`]
+#[derive(Debug, Clone)]
+pub struct Ints<'a, T> {
+    s: &'a str,
+    pos: usize,
+    _int: PhantomData<T>,
+}
+
+impl<'a, T: Integer> Ints<'a, T> {
+    /// The next integer's text, and the byte offset it starts at
+    fn next_text(&mut self) -> Option<(usize, &'a str)> {
+        let bytes = self.s.as_bytes();
+        while self.pos < bytes.len() {
+            let start = self.pos;
+            let digits = match bytes[start] {
+                b'0'..=b'9' => start,
+                // a dash between two words or numbers (ie: `1-3`) is not a minus sign
+                b'-' if bytes.get(start + 1).is_some_and(u8::is_ascii_digit)
+                    && ! (start > 0 && bytes[start - 1].is_ascii_alphanumeric()) => start + 1,
+                _ => {
+                    self.pos += 1;
+                    continue;
+                },
+            };
+            let end = bytes[digits..].iter().position(|b| ! b.is_ascii_digit()).map_or(bytes.len(), |len| digits + len);
+            self.pos = end;
+            return Some((start, &self.s[start..end]));
+        }
+        None
+    }
+
+    /// The next integer, or an error pointing at it if it does not fit in `T`
+    fn try_next(&mut self) -> Option<Result<T, ParseError>> {
+        let (start, text) = self.next_text()?;
+        Some(text.parse().map_err(|_| {
+            let column = self.s[..start].chars().count() + 1;
+            let msg = match text.starts_with('-') && ! T::SIGNED {
+                true => format!("{} is negative, which does not fit in {}", text, type_name::<T>()),
+                false => format!("{} does not fit in {}", text, type_name::<T>()),
+            };
+            ParseError::new(msg).with_text(self.s).with_column(column)
+        }))
+    }
+}
+
+impl<'a, T: Integer> Iterator for Ints<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.try_next().map(|int| int.unwrap_or_else(|e| panic!("{}", e)))
+    }
+}
+
+/// The integers within `s`, in order, without allocating.
+///
+/// A `-` directly before a number is read as its sign, unless it follows a letter or digit. (ie: `x=-2` is `-2`, but `1-3` is `1`
+/// and `3`)
+///
+/// Panics when iterated if a number does not fit in `T`, which includes negative numbers for unsigned types.
+pub fn ints<T: Integer>(s: &str) -> Ints<'_, T> {
+    Ints { s, pos: 0, _int: PhantomData }
+}
+
+/// The first `N` integers within `s`, or `None` if it has fewer.
+///
+/// Panics if one of them does not fit in `T`. (see [`first_ints_per_line`] to get an error instead)
+///
+/// ```
+/// use aoch::parsing::first_ints;
+///
+/// let [min, max] = first_ints::<usize, 2>("1-3 a: abcde").unwrap();
+/// assert_eq!((min, max), (1, 3));
+/// assert_eq!(first_ints::<i32, 3>("x=-2, y=15"), None);
+/// ```
+pub fn first_ints<T: Integer, const N: usize>(s: &str) -> Option<[T; N]> {
+    try_first_ints(s).map(|ints| ints.unwrap_or_else(|e| panic!("{}", e)))
+}
+
+fn try_first_ints<T: Integer, const N: usize>(s: &str) -> Option<Result<[T; N], ParseError>> {
+    let mut found = ints(s);
+    let mut arr = [None; N];
+    for slot in arr.iter_mut() {
+        match found.try_next()? {
+            Ok(int) => *slot = Some(int),
+            Err(e) => return Some(Err(e)),
+        }
+    }
+    Some(Ok(arr.map(|int| int.unwrap())))
+}
+
+/// The integers within each line with content, without allocating.
+pub fn ints_per_line<T: Integer>(input: &str) -> impl Iterator<Item = Ints<'_, T>> {
+    input.lines().filter_map(super::trimmed).map(ints)
+}
+
+/// The first `N` integers within each line with content, or an error for lines with fewer, or with one that does not fit in `T`.
+pub fn first_ints_per_line<T: Integer, const N: usize>(input: &str) -> impl Iterator<Item = Result<[T; N], ParseError>> + '_ {
+    super::numbered_lines(input)
+        .map(|(num, line)| match try_first_ints(line) {
+            Some(ints) => ints.map_err(|e| e.with_line(num, line)),
+            None => {
+                // all of them fit, or there would have been an error
+                let found = ints::<T>(line).count();
+                Err(ParseError::new(format!("expected {} integers, found {}", N, found)).with_line(num, line))
+            },
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs() {
+        let line = "Sensor at x=2, y=-18: closest beacon is at x=-2, y=15";
+        assert_eq!(ints::<i32>(line).collect::<Vec<_>>(), [2, -18, -2, 15]);
+        // a dash between words or numbers is not a sign
+        assert_eq!(ints::<i32>("1-3 a-5 -7 --8").collect::<Vec<_>>(), [1, 3, 5, -7, -8]);
+        assert_eq!(ints::<i64>("- -x 9-").collect::<Vec<_>>(), [9]);
+    }
+
+    #[test]
+    fn first() {
+        assert_eq!(first_ints::<i32, 2>("move 3 from 1 to 2"), Some([3, 1]));
+        assert_eq!(first_ints::<i32, 0>("none"), Some([]));
+        assert_eq!(first_ints::<i32, 2>("just 1"), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "300 does not fit in u8")]
+    fn overflow_panics() {
+        ints::<u8>("1 300").for_each(drop);
+    }
+
+    #[test]
+    #[should_panic(expected = "-2 is negative, which does not fit in u32")]
+    fn unsigned_negatives_panic() {
+        assert_eq!(ints::<u32>("1-3 x=2").collect::<Vec<_>>(), [1, 3, 2]);
+        ints::<u32>("x=-2").for_each(drop);
+    }
+
+    #[test]
+    fn per_line() {
+        let input = "1,2\n\n  3,4,5\n";
+        let lines: Vec<Vec<u8>> = ints_per_line(input).map(Iterator::collect).collect();
+        assert_eq!(lines, [vec![1, 2], vec![3, 4, 5]]);
+
+        let found: Result<Vec<[u8; 2]>, _> = first_ints_per_line(input).collect();
+        assert_eq!(found.unwrap(), [[1, 2], [3, 4]]);
+    }
+
+    #[test]
+    fn per_line_errors() {
+        let err = first_ints_per_line::<u8, 2>("1 2\nx=1 y=300\n").nth(1).unwrap().unwrap_err();
+        assert_eq!(err.error().to_string(), "300 does not fit in u8");
+        assert!(err.to_string().starts_with("line 2, column 7:"), "{}", err);
+
+        let err = first_ints_per_line::<u8, 2>("1 2\n3\n").nth(1).unwrap().unwrap_err();
+        assert_eq!(err.error().to_string(), "expected 2 integers, found 1");
+
+        let err = first_ints_per_line::<usize, 2>("x=1 y=-2\n").next().unwrap().unwrap_err();
+        assert_eq!((err.line, err.column), (Some(1), Some(7)));
+        assert_eq!(err.error().to_string(), "-2 is negative, which does not fit in usize");
+    }
+}
@@ -0,0 +1,136 @@
+//! Runtime support for `aoch::scan!`, which matches a line against a pattern of literal text and `{}` captures
+//!
+//! The pattern is checked against the capture types at compile time:
+//!
+//! ```
+//! let (min, max) = aoch::scan!("{}-{}", "1-3" => u8, u8).unwrap();
+//! # assert_eq!((min, max), (1, 3));
+//! ```
+//!
+//! So a capture without a type, or a type without a capture, fails to compile:
+//!
+//! ```compile_fail
+//! let (min, max) = aoch::scan!("{}-{}", "1-3" => u8).unwrap();
+//! ```
+//!
+//! ```compile_fail
+//! let (min, max, letter) = aoch::scan!("{}-{}", "1-3" => u8, u8, char).unwrap();
+//! ```
+//!
+//! As do captures with nothing between them to tell where one ends:
+//!
+//! ```compile_fail
+//! let (a, b) = aoch::scan!("{}{}", "13" => u8, u8).unwrap();
+//! ```
+
+use super::{FromLine, ParseError};
+
+/// Matches a line piece by piece, as generated by `scan!`
+#[doc(hidden)]
+pub struct Scanner<'i> {
+    input: &'i str,
+    pos: usize,
+}
+
+impl<'i> Scanner<'i> {
+    pub fn new(input: &'i str) -> Scanner<'i> {
+        Scanner { input, pos: 0 }
+    }
+
+    /// An error at byte `pos` of the line. Its line number is added by the parsing helper the line came from, if any.
+    fn error(&self, pos: usize, msg: String) -> ParseError {
+        ParseError::new(msg).with_text(self.input).with_column(self.input[..pos].chars().count() + 1)
+    }
+
+    /// Matches a literal piece of the pattern
+    pub fn literal(&mut self, lit: &str) -> Result<(), ParseError> {
+        if self.input[self.pos..].starts_with(lit) {
+            self.pos += lit.len();
+            Ok(())
+        } else {
+            Err(self.error(self.pos, format!("expected {:?}", lit)))
+        }
+    }
+
+    /// Takes the text up to the first `until` (the next literal in the pattern), or the rest of the line
+    pub fn capture(&mut self, until: Option<&str>) -> Result<&'i str, ParseError> {
+        let rest = &self.input[self.pos..];
+        let len = match until {
+            None => rest.len(),
+            Some(until) => rest.find(until)
+                .ok_or_else(|| self.error(self.pos, format!("expected {:?} after this", until)))?,
+        };
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    /// Takes a capture as with [`Scanner::capture`], and parses it as `T` (named `ty` in errors)
+    pub fn parse<T: FromLine<'i>>(&mut self, until: Option<&str>, ty: &str) -> Result<T, ParseError> {
+        let start = self.pos;
+        let text = self.capture(until)?;
+        T::from_line(text).map_err(|mut e| match e.column {
+            // already located within the capture, ie: by a nested `#[derive(aoch::Parse)]` type
+            Some(column) => {
+                e.text = self.input.to_string();
+                e.with_column(self.input[..start].chars().count() + column)
+            },
+            None => self.error(start, format!("invalid {} {:?}: {}", ty, text, e.error())),
+        })
+    }
+
+    /// Checks that the whole line was matched
+    pub fn end(&self) -> Result<(), ParseError> {
+        match &self.input[self.pos..] {
+            "" => Ok(()),
+            rest => Err(self.error(self.pos, format!("unexpected trailing text {:?}", rest))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// As `scan!("{}-{} {}: {}", line => usize, usize, char, &str)` would match
+    fn policy(line: &str) -> Result<(usize, usize, char, &str), ParseError> {
+        let mut scanner = Scanner::new(line);
+        let min = scanner.parse(Some("-"), "usize")?;
+        scanner.literal("-")?;
+        let max = scanner.parse(Some(" "), "usize")?;
+        scanner.literal(" ")?;
+        let letter = scanner.parse(Some(": "), "char")?;
+        scanner.literal(": ")?;
+        let password = scanner.capture(None)?;
+        scanner.end()?;
+        Ok((min, max, letter, password))
+    }
+
+    #[test]
+    fn matches() {
+        assert_eq!(policy("1-3 a: abcde").unwrap(), (1, 3, 'a', "abcde"));
+        assert_eq!(policy("1-3 a: ").unwrap(), (1, 3, 'a', ""));
+    }
+
+    #[test]
+    fn locates_errors() {
+        let err = policy("1-x a: abcde").unwrap_err();
+        assert!(err.to_string().starts_with("column 3: invalid usize \"x\""), "{}", err);
+        let err = policy("1-3 a abcde").unwrap_err();
+        assert!(err.to_string().starts_with("column 5: expected \": \" after this"), "{}", err);
+    }
+
+    #[test]
+    fn captures_to_the_first_literal() {
+        // no backtracking, so the first capture is empty rather than `-3`
+        let err = policy("-3-4 a: x").unwrap_err();
+        assert!(err.to_string().starts_with("column 1: invalid usize \"\""), "{}", err);
+    }
+
+    #[test]
+    fn checks_the_end() {
+        let mut scanner = Scanner::new("ab!");
+        scanner.literal("ab").unwrap();
+        let err = scanner.end().unwrap_err();
+        assert!(err.to_string().starts_with("column 3: unexpected trailing text \"!\""), "{}", err);
+    }
+}
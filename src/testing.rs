@@ -1,148 +1,832 @@
 use std::borrow::Cow;
 use std::fmt;
 use std::panic;
+use std::time::{Duration, Instant};
 
 use colored::Colorize;
-#[cfg(feature = "alloclog")] use tracking_allocator::{AllocationGroupToken, AllocationRegistry};
 
-use crate::{AoCDay, DayPart};
+use crate::{AoCDay, Answer1, Answer2, AnswerTypes, DayPart, Params, Part, Part1, Part2};
+use crate::aocday::{panic_message, Unsolved};
+
+mod diff;
+mod differential;
+mod examples;
+mod junit;
+mod snapshot;
+
+pub use differential::{char_grid, int_lines, CharGrid, Differential, Generator, IntLines, Rng};
+pub use examples::{load_examples, load_examples_from, test_example, test_examples, Example, ExpectedAnswer};
+pub use snapshot::run_snapshot;
 
 /// Lines shown if parsing/logic panics
 const LINES: usize = 10;
+/// Lines of the parsed data shown if a part panics
+const DATA_LINES: usize = 100;
+/// Set to dump the parsed data when a part panics
+const DUMP_DATA_VAR: &str = "AOCH_DUMP_DATA";
+/// Allocation sites shown when a case goes over its allocation budget
+#[cfg(feature = "alloclog")]
+const ALLOC_SITES: usize = 5;
 
-// Used to test a day's specific part
+// Used to test a day's specific part, selected at runtime.
+//
+// Cases are checked against the answer types of both parts, so days whose parts have different answer types (or tests wanting
+// named cases, parameters or several variants) should use `test_part` instead.
 pub fn test_runner<'i, Day, Ans>(day: Day, part: DayPart, cases: &[(&'i str, Ans)])
 where
-	Day: AoCDay,
-	Ans: ToString + fmt::Debug + PartialEq<Day::Answer> + Eq,
+	Day: AoCDay + 'i,
+	Ans: ToString + fmt::Debug + PartialEq<Answer1<Day>> + PartialEq<Answer2<Day>> + Eq,
+{
+	match part {
+		DayPart::Part1 => test_part(day, Part1, cases),
+		DayPart::Part2 => test_part(day, Part2, cases),
+	}
+}
+
+// Used to test a day's specific part, selected by its type (`Part1` or `Part2`). Cases are checked against the answer type of `part`.
+//
+// `days` may also be a tuple of several implementations of the same day, each of which is tested against every case.
+//
+// Cases are either `(input, expected)`, or `(input, params, expected)` for puzzles with parameters that differ from the real input.
+pub fn test_part<'i, Days, P, C>(days: Days, part: P, cases: &[C])
+where
+	Days: Variants + 'i,
+	P: Part + 'i,
+	C: TestCase<Input = &'i str>,
+	C::Expected: ToString + fmt::Debug + PartialEq<P::Answer<Days::Answer>> + Eq,
 {
-	// will drop
+	test_part_with(days, part, cases, &TestConfig::new())
+}
 
-	#[cfg(feature = "alloclog")] AllocationRegistry::set_global_tracker(crate::alloclog::MemoryStatistics::default()).unwrap();
-	#[cfg(feature = "alloclog")] AllocationRegistry::enable_tracking();
-	#[cfg(feature = "alloclog")] let mut alloctok = AllocationGroupToken::register().expect("unable to register memory allocator tracking");
+/// As [`test_part`], with extra checks set by `config` (ie: time budgets)
+pub fn test_part_with<'i, Days, P, C>(days: Days, part: P, cases: &[C], config: &TestConfig)
+where
+	Days: Variants + 'i,
+	P: Part + 'i,
+	C: TestCase<Input = &'i str>,
+	C::Expected: ToString + fmt::Debug + PartialEq<P::Answer<Days::Answer>> + Eq,
+{
+	#[cfg(feature = "alloclog")] crate::alloclog::install();
 
-	// #[cfg(feature = "alloclog")] // let alloctok = AllocationGroupId::ROOT;
-	#[cfg(feature = "alloclog")] let guard = alloctok.enter();
+	let solvers = days.solvers(part);
+	let several = solvers.len() > 1;
+	let mut failed = Vec::new();
+	for (name, solve) in solvers {
+		if several {
+			eprintln!("testing variant {}", name.bold());
+		}
 
-	run_test(|input| {
-		let mut data: Day::Data<'i> = match panic::catch_unwind(move || day.parse(input)) {
-			Ok(ds) => ds,
-			Err(e) => {
-				let input = (input.len() <= 200)
-					.then(|| *input).unwrap_or("<too long to display>");
-				panic!(
-					"panic while parsing input: `{}` (input = {:?})",
-					format!("{:?}", e).red(),
-					input.bold(),
-				)
+		let default_params = Params::new();
+		let suite = format!("day{:02}-part{}-{}", days.day(), P::PART as u8 + 1, name);
+		let checked = check_cases(cases, config, &suite, |case| {
+			solve(case.input(), case.params().unwrap_or(&default_params))
+		});
+		#[cfg(feature = "alloclog")] eprintln!("{}", crate::alloclog::summary(&format!("allocations of {}", suite)));
+		if let Err(failures) = checked {
+			failed.push((name, failures));
+		}
+	}
+
+	// every variant is checked before failing, so that one failing doesn't hide how the rest do
+	match &failed[..] {
+		[] => {},
+		[(_, failures)] if ! several => panic!("{}", failures),
+		_ => {
+			let names: Vec<&str> = failed.iter().map(|(name, _)| name.as_str()).collect();
+			let details: Vec<String> = failed.iter().map(|(name, failures)| format!("{}: {}", name.bold(), failures)).collect();
+			panic!("{} variants failed ({})\n\n{}", failed.len(), names.join(", "), details.join("\n\n"));
+		},
+	}
+}
+
+/// Parses an input with the given puzzle parameters, and solves one part of it, for a particular day variant
+pub type Solver<'i, A> = Box<dyn Fn(&'i str, &Params) -> A + 'i>;
+/// A variant's name, and its [`Solver`]
+pub type NamedSolver<'i, A> = (String, Solver<'i, A>);
+
+/// One or more implementations of the same day, to be tested against the same cases.
+///
+/// Implemented for any [`AoCDay`], and tuples of days sharing an answer type, ie: `(Day07Naive, Day07Fast)`.
+pub trait Variants: Copy {
+	/// The answer types shared by all variants.
+	type Answer: AnswerTypes;
+
+	/// The day's number.
+	fn day(self) -> u8;
+
+	/// Each variant's name, and a function to parse an input and solve `part` with it.
+	fn solvers<'i, P: Part + 'i>(self, part: P) -> Vec<NamedSolver<'i, P::Answer<Self::Answer>>>
+	where
+		Self: 'i;
+}
+
+impl<D: AoCDay> Variants for D {
+	type Answer = D::Answer;
+
+	fn day(self) -> u8 {
+		AoCDay::day(&self)
+	}
+
+	fn solvers<'i, P: Part + 'i>(self, part: P) -> Vec<NamedSolver<'i, P::Answer<Self::Answer>>>
+	where
+		Self: 'i,
+	{
+		vec![solver(self, part)]
+	}
+}
+
+macro_rules! impl_variants_tuple {
+	($first: ident $(, $rest: ident)*) => {
+		impl<$first: AoCDay, $($rest: AoCDay<Answer = $first::Answer>),*> Variants for ($first, $($rest),*) {
+			type Answer = $first::Answer;
+
+			fn day(self) -> u8 {
+				AoCDay::day(&self.0)
 			}
-		};
 
-		let result: Day::Answer = match part {
-			DayPart::Part1 => day.part1(&mut data),
-			DayPart::Part2 => day.part2(&mut data),
+			#[allow(non_snake_case)]
+			fn solvers<'i, P: Part + 'i>(self, part: P) -> Vec<NamedSolver<'i, P::Answer<Self::Answer>>>
+			where
+				Self: 'i,
+			{
+				let ($first, $($rest),*) = self;
+				vec![solver($first, part), $(solver($rest, part)),*]
+			}
+		}
+	};
+}
+impl_variants_tuple!(A, B);
+impl_variants_tuple!(A, B, C);
+impl_variants_tuple!(A, B, C, D);
+impl_variants_tuple!(A, B, C, D, E);
+impl_variants_tuple!(A, B, C, D, E, F);
+
+fn solver<'i, D: AoCDay + 'i, P: Part + 'i>(day: D, part: P) -> NamedSolver<'i, P::Answer<D::Answer>> {
+	let solve = move |input: &'i str, params: &Params| {
+		// the case's input is shown by check_cases, so only the panic itself is reported here
+		let mut data: D::Data<'i> = match panic::catch_unwind(move || crate::alloc_phase("parse", || day.parse_with(input, params))) {
+			Ok(ds) => ds,
+			Err(e) => panic::resume_unwind(Box::new(format!("panic while parsing input: {}", panic_message(&*e).red()))),
 		};
 
-		result
-	}, cases);
+		// rendered up front, as the part may leave the data half modified
+		let dump = std::env::var_os(DUMP_DATA_VAR).is_some()
+			.then(|| format!("{:#?}", data));
 
-	#[cfg(feature = "alloclog")]
-	guard.exit();
+		let solve = || crate::alloc_phase(&P::PART.to_string(), || part.solve(day, &mut data));
+		match panic::catch_unwind(panic::AssertUnwindSafe(solve)) {
+			Ok(answer) => answer,
+			Err(e) if e.is::<Unsolved>() => panic::resume_unwind(e),
+			Err(e) => {
+				let mut msg = format!("panic in {}: {}", P::PART, panic_message(&*e).red());
+				match dump {
+					Some(dump) => msg += &format!("\nparsed data:\n{}", truncate_lines(&dump, DATA_LINES)),
+					None => msg += &format!("\n(set {} to see the parsed data)", DUMP_DATA_VAR),
+				}
+				panic::resume_unwind(Box::new(msg))
+			},
+		}
+	};
+	(format!("{:?}", day), Box::new(solve))
 }
 
 // Used to test a specific function in a day
-pub fn run_test_pretty<I, E, O, F>(func: F, cases: &[(I, E)])
+pub fn run_test<C, O, F>(func: F, cases: &[C])
 where
-	I: fmt::Debug + Sized + ToString,
-	E: fmt::Debug + PartialEq<O> + Eq,
+	C: TestCase,
+	C::Input: TestInput,
+	C::Expected: fmt::Debug + PartialEq<O> + Eq,
 	O: fmt::Debug,
-	F: for<'a> Fn(&'a I) -> O,
+	F: for<'a> Fn(&'a C::Input) -> O,
 {
-	for (i, (case, expected)) in cases.iter().enumerate() {
-		let generated = func(case);
-		if *expected != generated {
-			// limit input string to 10 lines
-			let short_input: String = {
-				let input = case.to_string();
-				let mut newlines = input.char_indices()
-					.filter(|&(_, c)| c == '\n')
-					.skip(LINES);
-
-				match newlines.next() {
-					None => input,
-					Some((i, _)) => {
-						let rest = newlines.count();
-						let mut sinput = input[..i].to_string();
-						sinput += &format!("\n...<{} more lines>...", rest);
-						sinput
-					}
-				}
-			};
-
-			panic!(
-				"input #{} - '{}': expected `{}` got `{}` for input #{}",
-				i,
-				short_input.bold(),
-				format!("{:?}", expected).green(),
-				format!("{:?}", generated).red(),
-				i,
-			);
-		};
+	run_test_with(func, cases, &TestConfig::new())
+}
+
+/// As [`run_test`], with extra checks set by `config` (ie: time budgets)
+pub fn run_test_with<C, O, F>(func: F, cases: &[C], config: &TestConfig)
+where
+	C: TestCase,
+	C::Input: TestInput,
+	C::Expected: fmt::Debug + PartialEq<O> + Eq,
+	O: fmt::Debug,
+	F: for<'a> Fn(&'a C::Input) -> O,
+{
+	#[cfg(feature = "alloclog")] crate::alloclog::install();
+	if let Err(failures) = check_cases(cases, config, &junit::suite_of::<F>(), |case| func(case.input())) {
+		panic!("{}", failures);
+	}
+}
+
+/// Extra checks applied to every case of a test, for [`test_part_with`] and [`run_test_with`].
+///
+/// ```ignore
+/// // warn rather than fail in debug builds, where timings are meaningless
+/// let config = TestConfig::new().budget(Duration::from_millis(500)).warn_in_debug();
+/// test_part_with(Day07, Part2, &cases, &config);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TestConfig {
+	budget: Option<Duration>,
+	alloc_budget: Option<AllocBudget>,
+	warn_in_debug: bool,
+	junit: bool,
+}
+
+impl TestConfig {
+	pub const fn new() -> TestConfig {
+		TestConfig { budget: None, alloc_budget: None, warn_in_debug: false, junit: false }
+	}
+
+	/// Fails any case that takes longer than `budget` to parse and solve. Cases can set their own with [`Case::with_budget`].
+	pub fn budget(mut self, budget: Duration) -> TestConfig {
+		self.budget = Some(budget);
+		self
+	}
+
+	/// Fails any case that allocates more than `budget` allows while solving. Cases can set their own with [`Case::with_alloc_budget`].
+	///
+	/// Only checked with the `alloclog` feature enabled.
+	pub fn alloc_budget(mut self, budget: AllocBudget) -> TestConfig {
+		self.alloc_budget = Some(budget);
+		self
+	}
+
+	/// Only warns about cases that go over their time budget in debug builds, instead of failing them.
+	pub fn warn_in_debug(mut self) -> TestConfig {
+		self.warn_in_debug = true;
+		self
+	}
+
+	/// Writes each case's result as JUnit XML, to `target/aoch-reports/`. Also enabled for all tests by setting `AOCH_JUNIT=1`.
+	pub fn junit(mut self) -> TestConfig {
+		self.junit = true;
+		self
+	}
+
+	/// Whether a case over its budget is only warned about
+	fn budget_warns(&self) -> bool {
+		self.warn_in_debug && cfg!(debug_assertions)
+	}
+}
+
+/// Limits on the allocations made while solving a case, checked with the `alloclog` feature enabled.
+///
+/// For [`test_part`] cases this is the part alone, not parsing the input. For [`run_test`] it is the whole function.
+///
+/// ```ignore
+/// // the hot loop should not allocate at all
+/// Case::new(daystr!("07"), 1234).with_alloc_budget(AllocBudget::new().max_allocs(0))
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AllocBudget {
+	max_allocs: Option<usize>,
+	max_peak_bytes: Option<usize>,
+}
+
+impl AllocBudget {
+	pub const fn new() -> AllocBudget {
+		AllocBudget { max_allocs: None, max_peak_bytes: None }
+	}
+
+	/// Allows at most `count` allocations.
+	pub fn max_allocs(mut self, count: usize) -> AllocBudget {
+		self.max_allocs = Some(count);
+		self
+	}
+
+	/// Allows at most `bytes` to be allocated at once.
+	pub fn max_peak_bytes(mut self, bytes: usize) -> AllocBudget {
+		self.max_peak_bytes = Some(bytes);
+		self
+	}
+
+	/// The limits that `stats` goes over, ie: `12 allocs (max 0)`
+	#[cfg(feature = "alloclog")]
+	fn exceeded(&self, stats: &crate::alloclog::AllocStats) -> Vec<String> {
+		let mut over = Vec::new();
+		if let Some(max) = self.max_allocs.filter(|&max| stats.count > max) {
+			over.push(format!("{} allocs (max {})", stats.count, max));
+		}
+		if let Some(max) = self.max_peak_bytes.filter(|&max| stats.peak_bytes > max) {
+			over.push(format!("{} bytes peak (max {})", stats.peak_bytes, max));
+		}
+		over
+	}
+}
+
+/// A test case for [`run_test`] or [`test_part`].
+///
+/// Implemented for `(input, expected)`, and `(input, params, expected)` for puzzles with parameters that differ from the real input.
+pub trait TestCase {
+	type Input;
+	type Expected;
+
+	fn input(&self) -> &Self::Input;
+	fn expected(&self) -> &Self::Expected;
+
+	/// Puzzle parameters for this case. Parameters left unset use the real input's values.
+	fn params(&self) -> Option<&Params> {
+		None
+	}
+
+	/// A name for this case, shown in place of its index. (ie: "example 2" or "real input")
+	fn name(&self) -> Option<&str> {
+		None
+	}
+
+	/// The longest this case may take to parse and solve, overriding [`TestConfig::budget`].
+	fn budget(&self) -> Option<Duration> {
+		None
+	}
+
+	/// Limits on the allocations made while solving this case, overriding [`TestConfig::alloc_budget`].
+	fn alloc_budget(&self) -> Option<AllocBudget> {
+		None
+	}
+}
+
+impl<I, E> TestCase for (I, E) {
+	type Input = I;
+	type Expected = E;
+
+	fn input(&self) -> &I {
+		&self.0
+	}
+	fn expected(&self) -> &E {
+		&self.1
+	}
+}
+
+impl<I, E> TestCase for (I, Params, E) {
+	type Input = I;
+	type Expected = E;
+
+	fn input(&self) -> &I {
+		&self.0
+	}
+	fn expected(&self) -> &E {
+		&self.2
+	}
+	fn params(&self) -> Option<&Params> {
+		Some(&self.1)
+	}
+}
+
+/// A test case with an optional name and puzzle parameters, built up from [`Case::new`].
+///
+/// ```ignore
+/// let cases = [
+///     Case::new(TEST_INPUT, 6).named("example"),
+///     Case::new(daystr!("07"), 1234).named("real input"),
+/// ];
+/// ```
+#[derive(Debug, Clone)]
+pub struct Case<I, E> {
+	name: Option<String>,
+	input: I,
+	params: Option<Params>,
+	budget: Option<Duration>,
+	alloc_budget: Option<AllocBudget>,
+	expected: E,
+}
+
+impl<I, E> Case<I, E> {
+	pub fn new(input: I, expected: E) -> Case<I, E> {
+		Case { name: None, input, params: None, budget: None, alloc_budget: None, expected }
+	}
+
+	/// Names the case, as shown in test output.
+	pub fn named(mut self, name: &str) -> Case<I, E> {
+		self.name = Some(name.to_string());
+		self
+	}
+
+	/// Sets the case's puzzle parameters.
+	pub fn with_params(mut self, params: Params) -> Case<I, E> {
+		self.params = Some(params);
+		self
+	}
+
+	/// Fails the case if it takes longer than `budget` to parse and solve. (ie: 500ms for the real input in release)
+	pub fn with_budget(mut self, budget: Duration) -> Case<I, E> {
+		self.budget = Some(budget);
+		self
+	}
+
+	/// Fails the case if it allocates more than `budget` allows while solving. (see [`AllocBudget`])
+	pub fn with_alloc_budget(mut self, budget: AllocBudget) -> Case<I, E> {
+		self.alloc_budget = Some(budget);
+		self
+	}
+}
+
+impl<I, E> TestCase for Case<I, E> {
+	type Input = I;
+	type Expected = E;
+
+	fn input(&self) -> &I {
+		&self.input
+	}
+	fn expected(&self) -> &E {
+		&self.expected
+	}
+	fn params(&self) -> Option<&Params> {
+		self.params.as_ref()
+	}
+	fn name(&self) -> Option<&str> {
+		self.name.as_deref()
+	}
+	fn budget(&self) -> Option<Duration> {
+		self.budget
+	}
+	fn alloc_budget(&self) -> Option<AllocBudget> {
+		self.alloc_budget
 	}
 }
 
-pub fn run_test<'i, I, E, O, F>(func: F, cases: &[(I, E)])
+/// Checks every case against the output of `func`, catching panics per case.
+///
+/// Prints a summary table of all cases, and returns the failures (if any) as a message to panic with. If `func` is
+/// [`unsolved`](crate::unsolved), the remaining cases are skipped rather than failed.
+///
+/// `suite` names the test in JUnit reports. (see [`TestConfig::junit`])
+fn check_cases<C, O>(cases: &[C], config: &TestConfig, suite: &str, func: impl Fn(&C) -> O) -> Result<(), String>
 where
-	I: fmt::Debug + Sized + 'i,
-	E: fmt::Debug + PartialEq<O> + Eq,
+	C: TestCase,
+	C::Input: TestInput,
+	C::Expected: fmt::Debug + PartialEq<O> + Eq,
 	O: fmt::Debug,
-	F: for<'a> Fn(&'a I) -> O,
 {
-	for (i, (case, expected)) in cases.iter().enumerate() {
-		let generated = func(case);
-		if *expected != generated {
-			// limit input string to 10 lines
-			let short_input: Cow<str> = {
-				let input = DisplayInput::display(&case);
-
-				let mut newlines = input.char_indices()
-					.filter(|&(_, c)| c == '\n')
-					.skip(LINES);
-
-				match newlines.next() {
-					None => input,
-					Some((i, _)) => {
-						let rest = newlines.count();
-						let mut sinput = input[..i].to_string();
-						sinput += &format!("\n...<{} more lines>...", rest);
-						Cow::Owned(sinput)
-					}
-				}
-			};
-
-			panic!(
-				"input #{} - '{}': expected `{}` got `{}` for input #{}",
-				i,
-				short_input.bold(),
-				format!("{:?}", expected).green(),
-				format!("{:?}", generated).red(),
-				i,
-			);
+	let mut rows = Vec::with_capacity(cases.len());
+	let mut failures = Vec::new();
+	let mut skipped_from = None;
+	let row_label = |i: usize, case: &C| case.name().map(str::to_string).unwrap_or_else(|| format!("#{}", i));
+
+	#[cfg(not(feature = "alloclog"))]
+	if config.alloc_budget.is_some() || cases.iter().any(|case| case.alloc_budget().is_some()) {
+		eprintln!("{}", "warning: allocation budgets are only checked with the `alloclog` feature enabled".yellow());
+	}
+
+	for (i, case) in cases.iter().enumerate() {
+		let input = case.input().display();
+		let short_input = short_input(&input);
+		let params = match case.params() {
+			Some(params) if ! params.is_empty() => format!(" (params: {})", params),
+			_ => String::new(),
+		};
+		let label = match case.name() {
+			Some(name) => format!("'{}' (#{})", name, i),
+			None => format!("input #{}", i),
 		};
+
+		let start = Instant::now();
+		#[cfg(feature = "alloclog")]
+		let (generated, allocs) = crate::alloclog::measure_solve(|| panic::catch_unwind(panic::AssertUnwindSafe(|| func(case))));
+		#[cfg(not(feature = "alloclog"))]
+		let generated = panic::catch_unwind(panic::AssertUnwindSafe(|| func(case)));
+		let elapsed = start.elapsed();
+
+		let expected = diff::render(case.expected());
+		let (actual, mut failure) = match generated {
+			// the rest of the cases would be unsolved too
+			Err(e) if e.is::<Unsolved>() => {
+				skipped_from = Some(i);
+				break;
+			},
+			Err(e) => {
+				let msg = panic_message(&*e);
+				let failure = format!("{} - '{}'{}: panicked: {}", label, short_input.bold(), params, msg.red());
+				("<panicked>".to_string(), Some(failure))
+			},
+			Ok(generated) if *case.expected() == generated => (diff::render(&generated), None),
+			Ok(generated) => {
+				let actual = diff::render(&generated);
+				// multi-line answers are shown as blocks, with a diff
+				let failure = if diff::is_multiline(&expected, &actual) {
+					format!(
+						"{} - '{}'{}: wrong answer\n{}",
+						label,
+						short_input.bold(),
+						params,
+						diff::blocks(&expected, &actual),
+					)
+				} else {
+					format!(
+						"{} - '{}'{}: expected `{}` got `{}`",
+						label,
+						short_input.bold(),
+						params,
+						format!("{:?}", case.expected()).green(),
+						format!("{:?}", generated).red(),
+					)
+				};
+				(actual, Some(failure))
+			},
+		};
+
+		// only checked for correct answers, as a wrong one fails the case regardless
+		let budget = case.budget().or(config.budget);
+		let mut result = match failure {
+			None => CaseResult::Passed,
+			Some(_) => CaseResult::Failed,
+		};
+		if let Some(budget) = budget.filter(|&budget| failure.is_none() && elapsed > budget) {
+			let msg = format!("{} - '{}'{}: took {}, over its budget of {:?}", label, short_input.bold(), params, format!("{:?}", elapsed).red(), budget);
+			if config.budget_warns() {
+				eprintln!("{}", format!("warning: {} (ignored in debug builds)", msg).yellow());
+				result = CaseResult::Slow;
+			} else {
+				failure = Some(msg);
+				result = CaseResult::Failed;
+			}
+		}
+
+		#[cfg(feature = "alloclog")]
+		if let Some(alloc_budget) = case.alloc_budget().or(config.alloc_budget).filter(|_| failure.is_none()) {
+			let over = alloc_budget.exceeded(&allocs);
+			if ! over.is_empty() {
+				// run again to find where the allocations come from, as recording every call stack is far too slow to always do
+				let sites = crate::alloclog::top_sites(|| panic::catch_unwind(panic::AssertUnwindSafe(|| func(case))), ALLOC_SITES);
+				failure = Some(format!(
+					"{} - '{}'{}: over its allocation budget: {}\nsolving made {}\n{}",
+					label, short_input.bold(), params, over.join(", ").red(), allocs, sites,
+				));
+				result = CaseResult::Failed;
+			}
+		}
+
+		rows.push(CaseRow {
+			label: row_label(i, case),
+			input: cell(&input),
+			expected: cell(&expected),
+			actual: cell(&actual),
+			result,
+			elapsed,
+			budget,
+			failure: failure.clone(),
+		});
+		if let Some(failure) = failure {
+			failures.push((rows[i].label.clone(), failure));
+		}
+	}
+
+	if let Some(first) = skipped_from {
+		for (i, case) in cases.iter().enumerate().skip(first) {
+			rows.push(CaseRow {
+				label: row_label(i, case),
+				input: cell(&case.input().display()),
+				expected: cell(&diff::render(case.expected())),
+				actual: String::new(),
+				result: CaseResult::Skipped,
+				elapsed: Duration::ZERO,
+				budget: None,
+				failure: None,
+			});
+		}
 	}
+
+	eprintln!("{}", summary(&rows));
+	if config.junit || junit::enabled() {
+		junit::write_report(suite, &rows);
+	}
+
+	// libtest has no way to ignore a test at runtime, so it still shows as passing
+	if let Some(first) = skipped_from {
+		let skipped = cases.len() - first;
+		notice(&format!("{} is not solved yet - skipped {} of {} cases", suite, skipped, cases.len()).yellow().to_string());
+	}
+
+	if failures.is_empty() {
+		return Ok(());
+	}
+	let names: Vec<&str> = failures.iter().map(|(name, _)| name.as_str()).collect();
+	let details: Vec<&str> = failures.iter().map(|(_, failure)| failure.as_str()).collect();
+	Err(format!(
+		"{} of {} cases failed ({})\n\n{}",
+		failures.len(),
+		cases.len(),
+		names.join(", "),
+		details.join("\n\n"),
+	))
 }
 
-trait DisplayInput {
-	fn display<'i>(&'i self) -> Cow<'i, str>;
+/// A case's line in the summary table
+struct CaseRow {
+	label: String,
+	input: String,
+	expected: String,
+	actual: String,
+	result: CaseResult,
+	elapsed: Duration,
+	budget: Option<Duration>,
+	failure: Option<String>,
 }
-impl DisplayInput for &str {
-	fn display<'i>(&'i self) -> Cow<'i, str> {
-		Cow::Borrowed(self)
+
+enum CaseResult {
+	Passed,
+	Failed,
+	/// Passed, but went over its time budget in a debug build
+	Slow,
+	/// Not run, as the function is [`unsolved`](crate::unsolved)
+	Skipped,
+}
+
+/// Prints a line past libtest's output capturing, for things that should be seen even when the test passes.
+///
+/// libtest only captures the print macros, not writes to the stderr handle itself.
+fn notice(msg: &str) {
+	use std::io::Write;
+
+	let _ = writeln!(std::io::stderr(), "{}", msg);
+}
+
+/// Formats the summary table printed after checking all cases
+fn summary(rows: &[CaseRow]) -> String {
+	let header = ["case", "input", "expected", "actual"];
+	let width = |i: usize, col: fn(&CaseRow) -> &str| {
+		rows.iter().map(|r| col(r).chars().count()).chain([header[i].len()]).max().unwrap_or(0)
+	};
+	let widths = [
+		width(0, |r| &r.label),
+		width(1, |r| &r.input),
+		width(2, |r| &r.expected),
+		width(3, |r| &r.actual),
+	];
+
+	let mut out = format!(
+		"{:w0$}  {:w1$}  {:w2$}  {:w3$}  result   time",
+		header[0], header[1], header[2], header[3],
+		w0 = widths[0], w1 = widths[1], w2 = widths[2], w3 = widths[3],
+	).bold().to_string();
+	for row in rows {
+		let result = match row.result {
+			CaseResult::Passed => "ok     ".green(),
+			CaseResult::Failed => "FAILED ".red(),
+			CaseResult::Slow => "slow   ".yellow(),
+			CaseResult::Skipped => "skipped".yellow(),
+		};
+		let time = match (&row.result, row.budget) {
+			(CaseResult::Skipped, _) => "-".to_string(),
+			(_, Some(budget)) => format!("{:?} / {:?}", row.elapsed, budget),
+			(_, None) => format!("{:?}", row.elapsed),
+		};
+		out += &format!(
+			"\n{:w0$}  {:w1$}  {:w2$}  {:w3$}  {}  {}",
+			row.label, row.input, row.expected, row.actual, result, time,
+			w0 = widths[0], w1 = widths[1], w2 = widths[2], w3 = widths[3],
+		);
+	}
+	out
+}
+
+/// Limits an input to its first 10 lines
+fn short_input(input: &str) -> Cow<'_, str> {
+	truncate_lines(input, LINES)
+}
+
+fn truncate_lines(text: &str, lines: usize) -> Cow<'_, str> {
+	let mut newlines = text.char_indices()
+		.filter(|&(_, c)| c == '\n')
+		.skip(lines);
+
+	match newlines.next() {
+		None => Cow::Borrowed(text),
+		Some((i, _)) => {
+			let rest = newlines.count();
+			let mut stext = text[..i].to_string();
+			stext += &format!("\n...<{} more lines>...", rest);
+			Cow::Owned(stext)
+		}
+	}
+}
+
+/// Shortens a value to a single line, to fit in the summary table
+fn cell(value: &str) -> String {
+	const WIDTH: usize = 24;
+
+	let value = value.trim();
+	let lines = value.lines().count();
+	let first = value.lines().next().unwrap_or("");
+	let mut cell: String = first.chars().take(WIDTH).collect();
+	if first.chars().count() > WIDTH {
+		cell += "...";
+	}
+	if lines > 1 {
+		cell += &format!(" (+{} lines)", lines - 1);
 	}
+	cell
 }
-impl<T: fmt::Debug> DisplayInput for T {
-	default fn display<'i>(&'i self) -> Cow<'i, str> {
+
+/// How a test case's input is shown when the case fails.
+///
+/// Strings and primitives are shown as-is. Other input types can implement this with an empty `impl` block to use their `Debug` output.
+pub trait TestInput: fmt::Debug {
+	fn display(&self) -> Cow<'_, str> {
 		Cow::Owned(format!("{:?}", self))
 	}
 }
+
+impl TestInput for str {
+	fn display(&self) -> Cow<'_, str> {
+		Cow::Borrowed(self)
+	}
+}
+impl TestInput for String {
+	fn display(&self) -> Cow<'_, str> {
+		Cow::Borrowed(self)
+	}
+}
+impl<T: TestInput + ?Sized> TestInput for &T {
+	fn display(&self) -> Cow<'_, str> {
+		(**self).display()
+	}
+}
+
+macro_rules! impl_test_input_display {
+	($($t: ty),*) => {
+		$(
+			impl TestInput for $t {
+				fn display(&self) -> Cow<'_, str> {
+					Cow::Owned(self.to_string())
+				}
+			}
+		)*
+	};
+}
+impl_test_input_display!(char, bool, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+impl<T: fmt::Debug> TestInput for [T] {}
+impl<T: fmt::Debug> TestInput for Vec<T> {}
+impl<T: fmt::Debug, const N: usize> TestInput for [T; N] {}
+impl<T: fmt::Debug> TestInput for Option<T> {}
+impl<A: fmt::Debug, B: fmt::Debug> TestInput for (A, B) {}
+impl<A: fmt::Debug, B: fmt::Debug, C: fmt::Debug> TestInput for (A, B, C) {}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::cell::Cell;
+	use crate::unsolved;
+
+	#[test]
+	fn unsolved_skips_remaining_cases() {
+		let calls = Cell::new(0);
+		let doubled = |case: &(u32, u32)| {
+			calls.set(calls.get() + 1);
+			if case.0 > 1 {
+				unsolved()
+			}
+			case.0 * 2
+		};
+		assert_eq!(check_cases(&[(1, 2), (2, 4), (3, 6)], &TestConfig::new(), "unsolved", doubled), Ok(()));
+		assert_eq!(calls.get(), 2);
+
+		// cases before it are still checked
+		let err = check_cases(&[(1, 3), (2, 4)], &TestConfig::new(), "unsolved", doubled).unwrap_err();
+		assert!(err.starts_with("1 of 2 cases failed (#0)"), "{}", err);
+	}
+
+	#[test]
+	fn lists_every_failure() {
+		let cases = [Case::new(1, 2).named("one"), Case::new(2, 5), Case::new(3, 7), Case::new(4, 8)];
+		let err = check_cases(&cases, &TestConfig::new(), "failures", |case| match case.input() {
+			3 => panic!("three"),
+			n => n * 2,
+		}).unwrap_err();
+
+		assert!(err.starts_with("2 of 4 cases failed (#1, #2)"), "{}", err);
+		let details: Vec<&str> = err.split("\n\n").skip(1).collect();
+		assert_eq!(details.len(), 2, "{}", err);
+		assert!(details[0].starts_with("input #1") && details[0].contains("expected `"), "{}", err);
+		assert!(details[1].starts_with("input #2") && details[1].contains("panicked: "), "{}", err);
+
+		// only panicking once every case was checked
+		let panicked = panic::catch_unwind(|| run_test(|n: &u32| n * 2, &[(1, 3), (2, 5)])).unwrap_err();
+		assert_eq!(panic_message(&*panicked).lines().next(), Some("2 of 2 cases failed (#0, #1)"));
+	}
+
+
+	#[test]
+	fn budgets() {
+		fn slow<C>(_case: &C) -> u32 {
+			std::thread::sleep(Duration::from_millis(20));
+			1
+		}
+		let config = TestConfig::new().budget(Duration::from_millis(1));
+
+		let err = check_cases(&[(0, 1)], &config, "budget", slow).unwrap_err();
+		assert!(err.contains("over its budget of 1ms"), "{}", err);
+		// a wrong answer is reported as such, rather than as being slow
+		let err = check_cases(&[(0, 2)], &config, "budget", slow).unwrap_err();
+		assert!(! err.contains("budget"), "{}", err);
+
+		// a case's own budget overrides the test's
+		let cases = [Case::new(0, 1).with_budget(Duration::from_secs(60))];
+		assert_eq!(check_cases(&cases, &config, "budget", slow), Ok(()));
+
+		// only warned about in debug builds
+		let checked = check_cases(&[(0, 1)], &config.clone().warn_in_debug(), "budget", slow);
+		assert_eq!(checked.is_ok(), cfg!(debug_assertions), "{:?}", checked);
+	}
+
+}
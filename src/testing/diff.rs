@@ -0,0 +1,156 @@
+//! Failure output for multi-line answers (ie: rendered grids or letter art)
+
+use std::fmt::Write;
+
+use colored::Colorize;
+
+/// Renders an answer via its `Debug` output, with string answers unquoted and unescaped so that their lines can be shown as-is
+pub(crate) fn render<T: std::fmt::Debug + ?Sized>(value: &T) -> String {
+	unquote(format!("{:?}", value))
+}
+
+/// As [`render`], using the pretty `{:#?}` format for anything that isn't a string
+pub(crate) fn render_pretty<T: std::fmt::Debug + ?Sized>(value: &T) -> String {
+	unquote(format!("{:#?}", value))
+}
+
+fn unquote(debug: String) -> String {
+	match debug.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+		Some(inner) => unescape(inner),
+		None => debug,
+	}
+}
+
+/// Whether either answer spans several lines, and so should be shown as blocks rather than inline
+pub(crate) fn is_multiline(expected: &str, actual: &str) -> bool {
+	expected.trim_end().contains('\n') || actual.trim_end().contains('\n')
+}
+
+/// Shows both answers as colored blocks, followed by a line-by-line diff with the mismatched characters highlighted
+pub(crate) fn blocks(expected: &str, actual: &str) -> String {
+	let mut out = String::new();
+
+	let _ = writeln!(out, "{}", "expected:".bold());
+	for line in expected.lines() {
+		let _ = writeln!(out, "  {}", line.green());
+	}
+	let _ = writeln!(out, "{}", "got:".bold());
+	for line in actual.lines() {
+		let _ = writeln!(out, "  {}", line.red());
+	}
+
+	let _ = writeln!(out, "{}", "diff:".bold());
+	let mut expected_lines = expected.lines();
+	let mut actual_lines = actual.lines();
+	loop {
+		match (expected_lines.next(), actual_lines.next()) {
+			(None, None) => break,
+			(Some(exp), Some(act)) if exp == act => {
+				let _ = writeln!(out, "  {}", exp.dimmed());
+			},
+			(exp, act) => {
+				if let Some(exp) = exp {
+					let _ = writeln!(out, "{} {}", "-".green(), highlight(exp, act.unwrap_or(""), true));
+				}
+				if let Some(act) = act {
+					let _ = writeln!(out, "{} {}", "+".red(), highlight(act, exp.unwrap_or(""), false));
+				}
+			},
+		}
+	}
+	out
+}
+
+/// Colors `line`, highlighting every character that differs from the one at the same position in `other`
+fn highlight(line: &str, other: &str, expected: bool) -> String {
+	let mut other = other.chars();
+	let mut out = String::new();
+	for c in line.chars() {
+		let s = c.to_string();
+		let colored = match (other.next() == Some(c), expected) {
+			(true, true) => s.green(),
+			(true, false) => s.red(),
+			(false, true) => s.black().on_green(),
+			(false, false) => s.black().on_red(),
+		};
+		let _ = write!(out, "{}", colored);
+	}
+	out
+}
+
+/// Reverses the escaping done by `str`'s `Debug` impl
+fn unescape(s: &str) -> String {
+	let mut out = String::with_capacity(s.len());
+	let mut chars = s.chars();
+	while let Some(c) = chars.next() {
+		if c != '\\' {
+			out.push(c);
+			continue;
+		}
+		match chars.next() {
+			Some('n') => out.push('\n'),
+			Some('r') => out.push('\r'),
+			Some('t') => out.push('\t'),
+			Some('0') => out.push('\0'),
+			Some('u') => {
+				// `\u{XXXX}`
+				let code: String = chars.by_ref()
+					.skip(1)
+					.take_while(|&c| c != '}')
+					.collect();
+				match u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+					Some(c) => out.push(c),
+					None => {
+						let _ = write!(out, "\\u{{{}}}", code);
+					},
+				}
+			},
+			Some(c) => out.push(c),
+			None => out.push('\\'),
+		}
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::junit::strip_colors;
+
+	#[test]
+	fn renders_strings_as_is() {
+		assert_eq!(render("a\nb\t\"c\" \\ é"), "a\nb\t\"c\" \\ é");
+		assert_eq!(render("\u{1b}[0m\0"), "\u{1b}[0m\0");
+		assert_eq!(render(&5), "5");
+		assert_eq!(render(&Some("x")), "Some(\"x\")");
+		assert_eq!(render_pretty(&[1, 2]), "[\n    1,\n    2,\n]");
+		assert_eq!(render_pretty("a\nb"), "a\nb");
+	}
+
+	#[test]
+	fn multiline() {
+		assert!(is_multiline("#.\n.#", "#."));
+		assert!(is_multiline("#.", ".#\n#."));
+		assert!(! is_multiline("12\n", "13"));
+	}
+
+	#[test]
+	fn blocks_and_diff() {
+		let out = strip_colors(&blocks("#..\n.#.\n..#", "#..\n.##"));
+		assert_eq!(out, [
+			"expected:", "  #..", "  .#.", "  ..#",
+			"got:", "  #..", "  .##",
+			"diff:", "  #..", "- .#.", "+ .##", "- ..#", "",
+		].join("\n"));
+	}
+
+	#[test]
+	fn highlights_differences() {
+		colored::control::set_override(true);
+		let expected = format!("{}{}{}", "a".green(), "b".black().on_green(), "c".green());
+		assert_eq!(highlight("abc", "axc", true), expected);
+		let actual = format!("{}{}{}", "a".red(), "x".black().on_red(), "y".black().on_red());
+		assert_eq!(highlight("axy", "a", false), actual);
+		colored::control::unset_override();
+	}
+}
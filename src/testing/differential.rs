@@ -0,0 +1,483 @@
+//! Differential testing, checking solutions against each other (ie: a fast one against a brute force one) on random inputs
+//!
+//! ```ignore
+//! use aoch::testing::{Differential, int_lines};
+//!
+//! Differential::new(int_lines(1..=20, -50..=50))
+//!     .cases(500)
+//!     .check_days((Day07Naive, Day07Fast), Part1);
+//! ```
+
+use std::cell::Cell;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::panic;
+use std::sync::Once;
+
+use colored::Colorize;
+
+use crate::aocday::panic_message;
+use crate::{Params, Part};
+use super::{short_input, Variants};
+
+/// Set to override the seed of the first random input
+const SEED_VAR: &str = "AOCH_SEED";
+/// Limit on inputs tried while shrinking, in case a shrink never settles
+const SHRINK_ATTEMPTS: usize = 2000;
+
+/// A small, seeded random number generator (SplitMix64), so that failing inputs can be reproduced from their seed.
+#[derive(Debug, Clone)]
+pub struct Rng {
+	state: u64,
+}
+
+impl Rng {
+	pub fn new(seed: u64) -> Rng {
+		Rng { state: seed }
+	}
+
+	pub fn next_u64(&mut self) -> u64 {
+		self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+		let mut z = self.state;
+		z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+		z ^ (z >> 31)
+	}
+
+	/// A random integer within `range`
+	pub fn int(&mut self, range: RangeInclusive<i64>) -> i64 {
+		let (start, end) = range.into_inner();
+		assert!(start <= end, "empty range {}..={}", start, end);
+		let span = (end as i128 - start as i128 + 1) as u128;
+		(start as i128 + (self.next_u64() as u128 % span) as i128) as i64
+	}
+
+	/// A random size within `range`
+	pub fn size(&mut self, range: RangeInclusive<usize>) -> usize {
+		self.int(*range.start() as i64..=*range.end() as i64) as usize
+	}
+
+	/// A random element of `items`
+	pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+		&items[self.size(0..=items.len() - 1)]
+	}
+}
+
+/// Generates random inputs, and proposes smaller versions of an input that fails.
+///
+/// Implemented for closures taking an [`Rng`], which shrink by removing lines.
+pub trait Generator {
+	fn generate(&self, rng: &mut Rng) -> String;
+
+	/// Smaller versions of `input` to try, simplest first.
+	fn shrink(&self, input: &str) -> Vec<String> {
+		let lines: Vec<&str> = input.lines().collect();
+		without_lines(&lines, 0)
+			.into_iter()
+			.map(|lines| lines.join("\n"))
+			.collect()
+	}
+}
+
+impl<F: Fn(&mut Rng) -> String> Generator for F {
+	fn generate(&self, rng: &mut Rng) -> String {
+		self(rng)
+	}
+}
+
+/// Lines of separated integers. See [`int_lines`].
+#[derive(Debug, Clone)]
+pub struct IntLines {
+	lines: RangeInclusive<usize>,
+	per_line: RangeInclusive<usize>,
+	values: RangeInclusive<i64>,
+	separator: String,
+}
+
+/// A number of lines within `lines`, each of a single integer within `values`. Use [`IntLines::per_line`] for several per line.
+pub fn int_lines(lines: RangeInclusive<usize>, values: RangeInclusive<i64>) -> IntLines {
+	IntLines { lines, per_line: 1..=1, values, separator: " ".to_string() }
+}
+
+impl IntLines {
+	/// Sets how many integers are on each line.
+	pub fn per_line(mut self, per_line: RangeInclusive<usize>) -> IntLines {
+		self.per_line = per_line;
+		self
+	}
+
+	/// Sets what separates the integers on a line. (default: a space)
+	pub fn separator(mut self, separator: &str) -> IntLines {
+		self.separator = separator.to_string();
+		self
+	}
+
+	fn parse(&self, input: &str) -> Vec<Vec<i64>> {
+		input.lines()
+			.map(|line| line.split(self.separator.as_str()).filter_map(|n| n.trim().parse().ok()).collect())
+			.collect()
+	}
+
+	fn render(&self, lines: &[Vec<i64>]) -> String {
+		lines.iter()
+			.map(|line| line.iter().map(i64::to_string).collect::<Vec<_>>().join(&self.separator))
+			.collect::<Vec<_>>()
+			.join("\n")
+	}
+}
+
+impl Generator for IntLines {
+	fn generate(&self, rng: &mut Rng) -> String {
+		let lines: Vec<Vec<i64>> = (0..rng.size(self.lines.clone()))
+			.map(|_| (0..rng.size(self.per_line.clone())).map(|_| rng.int(self.values.clone())).collect())
+			.collect();
+		self.render(&lines)
+	}
+
+	fn shrink(&self, input: &str) -> Vec<String> {
+		let lines = self.parse(input);
+		let mut candidates: Vec<Vec<Vec<i64>>> = without_lines(&lines, *self.lines.start());
+
+		// then fewer integers on a line, and integers closer to zero
+		let target = 0.clamp(*self.values.start(), *self.values.end());
+		for (i, line) in lines.iter().enumerate() {
+			for shorter in without_lines(line, *self.per_line.start()) {
+				let mut smaller = lines.clone();
+				smaller[i] = shorter;
+				candidates.push(smaller);
+			}
+			for (j, &n) in line.iter().enumerate() {
+				// widened, as the distance between values can overflow
+				let halfway = (target as i128 + (n as i128 - target as i128) / 2) as i64;
+				for closer in [target, halfway] {
+					if closer != n {
+						let mut smaller = lines.clone();
+						smaller[i][j] = closer;
+						candidates.push(smaller);
+					}
+				}
+			}
+		}
+		candidates.iter().map(|lines| self.render(lines)).collect()
+	}
+}
+
+/// A rectangular grid of characters. See [`char_grid`].
+#[derive(Debug, Clone)]
+pub struct CharGrid {
+	rows: RangeInclusive<usize>,
+	cols: RangeInclusive<usize>,
+	alphabet: Vec<char>,
+}
+
+/// A grid of `rows` by `cols` characters, picked from `alphabet`. Shrinking favours the alphabet's first character. (ie: `".#"`)
+pub fn char_grid(rows: RangeInclusive<usize>, cols: RangeInclusive<usize>, alphabet: &str) -> CharGrid {
+	let alphabet: Vec<char> = alphabet.chars().collect();
+	assert!(! alphabet.is_empty(), "char_grid needs at least one character to pick from");
+	CharGrid { rows, cols, alphabet }
+}
+
+impl Generator for CharGrid {
+	fn generate(&self, rng: &mut Rng) -> String {
+		let (rows, cols) = (rng.size(self.rows.clone()), rng.size(self.cols.clone()));
+		(0..rows)
+			.map(|_| (0..cols).map(|_| *rng.choose(&self.alphabet)).collect::<String>())
+			.collect::<Vec<_>>()
+			.join("\n")
+	}
+
+	fn shrink(&self, input: &str) -> Vec<String> {
+		let grid: Vec<Vec<char>> = input.lines().map(|line| line.chars().collect()).collect();
+		let width = grid.first().map_or(0, Vec::len);
+
+		let mut candidates = without_lines(&grid, *self.rows.start());
+		// remove a column
+		if width > *self.cols.start() {
+			for col in 0..width {
+				candidates.push(grid.iter().map(|row| [&row[..col], &row[col + 1..]].concat()).collect());
+			}
+		}
+		// simplify a character
+		let simplest = self.alphabet[0];
+		for (y, row) in grid.iter().enumerate() {
+			for (x, &c) in row.iter().enumerate() {
+				if c != simplest {
+					let mut simpler = grid.clone();
+					simpler[y][x] = simplest;
+					candidates.push(simpler);
+				}
+			}
+		}
+		candidates.iter()
+			.map(|grid| grid.iter().map(|row| row.iter().collect::<String>()).collect::<Vec<_>>().join("\n"))
+			.collect()
+	}
+}
+
+/// Copies of `items` with some removed, keeping at least `min`: first halves, then single items
+fn without_lines<T: Clone>(items: &[T], min: usize) -> Vec<Vec<T>> {
+	let mut candidates = Vec::new();
+	let half = items.len() / 2;
+	if half > 0 && items.len() - half >= min {
+		candidates.push(items[half..].to_vec());
+		candidates.push(items[..items.len() - half].to_vec());
+	}
+	if items.len() > min {
+		for i in 0..items.len() {
+			candidates.push([&items[..i], &items[i + 1..]].concat());
+		}
+	}
+	candidates
+}
+
+/// Runs solutions against many random inputs, checking that they agree.
+///
+/// Answers are compared by their `Debug` output. A solution panicking where another doesn't is also a disagreement.
+/// On a disagreement, the input is shrunk to a minimal one that still disagrees, which is reported along with its seed.
+pub struct Differential<G> {
+	generator: G,
+	cases: usize,
+	seed: u64,
+	params: Params,
+}
+
+impl<G: Generator> Differential<G> {
+	/// Runs 100 inputs by default, starting from seed 0.
+	pub fn new(generator: G) -> Differential<G> {
+		Differential { generator, cases: 100, seed: 0, params: Params::new() }
+	}
+
+	/// Sets the number of random inputs to try.
+	pub fn cases(mut self, cases: usize) -> Differential<G> {
+		self.cases = cases;
+		self
+	}
+
+	/// Sets the seed of the first random input. Each following input uses the next seed.
+	///
+	/// Overridden by the `AOCH_SEED` environment variable, to reproduce a failure.
+	pub fn seed(mut self, seed: u64) -> Differential<G> {
+		self.seed = seed;
+		self
+	}
+
+	/// Sets the puzzle parameters that days are run with, in [`Differential::check_days`].
+	pub fn params(mut self, params: Params) -> Differential<G> {
+		self.params = params;
+		self
+	}
+
+	/// Checks that two functions agree on every input.
+	pub fn check<A, B, O>(&self, a: A, b: B)
+	where
+		A: Fn(&str) -> O,
+		B: Fn(&str) -> O,
+		O: fmt::Debug,
+	{
+		self.run(|input| vec![
+			("a".to_string(), outcome(|| a(input))),
+			("b".to_string(), outcome(|| b(input))),
+		]);
+	}
+
+	/// Checks that several variants of a day agree on `part` for every input. (ie: `(Day07Naive, Day07Fast)`)
+	pub fn check_days<Days: Variants, P: Part>(&self, days: Days, part: P) {
+		self.run(|input| days.solvers(part)
+			.into_iter()
+			.map(|(name, solve)| (name, outcome(|| solve(input, &self.params))))
+			.collect());
+	}
+
+	fn run(&self, solve: impl Fn(&str) -> Vec<(String, Outcome)>) {
+		let start = match std::env::var(SEED_VAR) {
+			Ok(seed) => seed.parse().unwrap_or_else(|e| panic!("invalid {} ({:?}): {}", SEED_VAR, seed, e)),
+			Err(_) => self.seed,
+		};
+		for i in 0..self.cases {
+			let seed = start.wrapping_add(i as u64);
+			let input = self.generator.generate(&mut Rng::new(seed));
+			if ! disagrees(&solve(&input)) {
+				continue;
+			}
+
+			let shrunk = quietly(|| self.shrink(input.clone(), &solve));
+			let outcomes = solve(&shrunk)
+				.into_iter()
+				.map(|(name, outcome)| format!("\t{}: {}", name, outcome))
+				.collect::<Vec<_>>()
+				.join("\n");
+			panic!(
+				"solutions disagree on random input #{} (seed {}, set {}={} to start from it)\nshrunk from {} to {} lines: '{}'\n{}",
+				i,
+				seed,
+				SEED_VAR,
+				seed,
+				input.lines().count(),
+				shrunk.lines().count(),
+				short_input(&shrunk).bold(),
+				outcomes,
+			);
+		}
+	}
+
+	/// Repeatedly takes the first smaller input that still disagrees
+	fn shrink(&self, mut input: String, solve: &impl Fn(&str) -> Vec<(String, Outcome)>) -> String {
+		let mut attempts = 0;
+		'shrinking: while attempts < SHRINK_ATTEMPTS {
+			for candidate in self.generator.shrink(&input) {
+				attempts += 1;
+				if disagrees(&solve(&candidate)) {
+					input = candidate;
+					continue 'shrinking;
+				}
+				if attempts >= SHRINK_ATTEMPTS {
+					break;
+				}
+			}
+			break;
+		}
+		input
+	}
+}
+
+thread_local! {
+	/// Set while inside [`quietly`], for the panic hook to skip this thread's panics
+	static QUIET: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Runs `f` without printing this thread's panics, as shrinking expects many of them. (and reports them itself)
+///
+/// The panic hook is only wrapped once, to skip panics on threads inside `quietly`. Other threads' panics, ie: those of other tests,
+/// are still printed by the previous hook.
+fn quietly<R>(f: impl FnOnce() -> R) -> R {
+	static INSTALL: Once = Once::new();
+	INSTALL.call_once(|| {
+		let previous = panic::take_hook();
+		panic::set_hook(Box::new(move |info| if ! QUIET.with(Cell::get) {
+			previous(info)
+		}));
+	});
+
+	let was_quiet = QUIET.with(|quiet| quiet.replace(true));
+	let res = panic::catch_unwind(panic::AssertUnwindSafe(f));
+	QUIET.with(|quiet| quiet.set(was_quiet));
+	res.unwrap_or_else(|e| panic::resume_unwind(e))
+}
+
+/// A solution's answer (as `Debug` output) or panic message
+enum Outcome {
+	Answer(String),
+	Panicked(String),
+}
+
+impl fmt::Display for Outcome {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Outcome::Answer(ans) => write!(f, "{}", ans.green()),
+			Outcome::Panicked(msg) => write!(f, "{} {}", "panicked:".red(), msg),
+		}
+	}
+}
+
+fn outcome<O: fmt::Debug>(f: impl FnOnce() -> O) -> Outcome {
+	match panic::catch_unwind(panic::AssertUnwindSafe(f)) {
+		Ok(ans) => Outcome::Answer(format!("{:?}", ans)),
+		Err(e) => Outcome::Panicked(panic_message(&*e)),
+	}
+}
+
+/// Whether any solution's answer differs from the first's. Solutions that all panic are taken to agree that the input is invalid.
+fn disagrees(outcomes: &[(String, Outcome)]) -> bool {
+	let first = match outcomes.first() {
+		Some((_, first)) => first,
+		None => return false,
+	};
+	outcomes.iter().any(|(_, outcome)| match (first, outcome) {
+		(Outcome::Answer(a), Outcome::Answer(b)) => a != b,
+		(Outcome::Panicked(_), Outcome::Panicked(_)) => false,
+		_ => true,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rng_is_seeded() {
+		let mut a = Rng::new(7);
+		let mut b = Rng::new(7);
+		for _ in 0..100 {
+			let n = a.int(-3..=3);
+			assert_eq!(n, b.int(-3..=3));
+			assert!((-3..=3).contains(&n));
+		}
+		assert_eq!(Rng::new(1).int(i64::MIN..=i64::MAX), Rng::new(1).int(i64::MIN..=i64::MAX));
+	}
+
+	#[test]
+	fn shrinks_lines_and_values() {
+		let candidates = int_lines(1..=5, -10..=10).shrink("4\n-9");
+		assert!(candidates.contains(&"-9".to_string()));
+		assert!(candidates.contains(&"4".to_string()));
+		assert!(candidates.contains(&"0\n-9".to_string()));
+		assert!(candidates.contains(&"4\n-4".to_string()));
+		// never below the minimum number of lines
+		assert!(int_lines(2..=5, -10..=10).shrink("4\n-9").iter().all(|c| c.lines().count() == 2));
+	}
+
+	#[test]
+	fn shrinks_extreme_values() {
+		let candidates = int_lines(1..=1, 1..=i64::MAX).shrink(&i64::MAX.to_string());
+		assert_eq!(candidates, ["1".to_string(), (i64::MAX / 2 + 1).to_string()]);
+		let candidates = int_lines(1..=1, i64::MIN..=-1).shrink(&i64::MIN.to_string());
+		assert_eq!(candidates, ["-1".to_string(), (i64::MIN / 2).to_string()]);
+	}
+
+	#[test]
+	fn shrinks_grids() {
+		let candidates = char_grid(1..=3, 1..=3, ".#").shrink("#.\n..");
+		assert!(candidates.contains(&"..".to_string()));
+		assert!(candidates.contains(&".\n.".to_string()));
+		assert!(candidates.contains(&"..\n..".to_string()));
+	}
+
+	#[test]
+	fn panics_disagree_with_answers() {
+		let answer = |ans: &str| Outcome::Answer(ans.to_string());
+		let panicked = || Outcome::Panicked("boom".to_string());
+		assert!(! disagrees(&[("a".to_string(), answer("1")), ("b".to_string(), answer("1"))]));
+		assert!(disagrees(&[("a".to_string(), answer("1")), ("b".to_string(), answer("2"))]));
+		assert!(disagrees(&[("a".to_string(), answer("1")), ("b".to_string(), panicked())]));
+		assert!(! disagrees(&[("a".to_string(), panicked()), ("b".to_string(), panicked())]));
+	}
+
+	#[test]
+	fn reports_shrunk_input() {
+		let big = |input: &str| input.lines().any(|line| line.parse::<i64>().unwrap() >= 50);
+		let res = panic::catch_unwind(|| {
+			Differential::new(int_lines(5..=20, 0..=100)).check(big, |_| false);
+		});
+		let msg = panic_message(&*res.unwrap_err());
+		assert!(msg.contains("to 5 lines"), "{}", msg);
+	}
+
+	#[test]
+	fn agreeing_solutions_pass() {
+		Differential::new(char_grid(1..=5, 1..=5, ".#"))
+			.cases(20)
+			.check(|input| input.matches('#').count(), |input| input.chars().filter(|&c| c == '#').count());
+	}
+
+	#[test]
+	fn quiet_panics() {
+		let caught = quietly(|| quietly(|| panic::catch_unwind(|| panic!("shrinking"))).is_err());
+		assert!(caught);
+		assert!(! QUIET.with(Cell::get));
+
+		// panics escaping it are passed on, with the thread no longer quiet
+		assert!(panic::catch_unwind(|| quietly(|| panic!("escaped"))).is_err());
+		assert!(! QUIET.with(Cell::get));
+	}
+
+}
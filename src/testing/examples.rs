@@ -0,0 +1,260 @@
+//! Example test cases read from files, rather than inline string literals
+//!
+//! Examples live in an `examples/` directory next to `input/`, named by day and optionally a name of their own, ie: `07.txt` or
+//! `07-larger.txt`. Each file is the example's raw input, optionally preceded by a header block of its answers and puzzle
+//! parameters, ended by a `---` line:
+//!
+//! ```text
+//! part1: 6
+//! part2: 12
+//! params: steps=10, size=7
+//! ---
+//! <raw input>
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use colored::Colorize;
+
+use crate::{DayPart, Params, Part};
+use super::{notice, test_part, Case, Variants};
+
+/// Where examples are loaded from, relative to the day crate
+const DIR: &str = "../examples";
+/// Keys allowed in an example's header block
+const KEYS: [&str; 3] = ["part1", "part2", "params"];
+
+/// An example input, with any answers and puzzle parameters from its header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Example {
+	/// The file's name, without its extension. (ie: `07-larger`)
+	pub name: String,
+	pub input: String,
+	pub part1: Option<ExpectedAnswer>,
+	pub part2: Option<ExpectedAnswer>,
+	pub params: Params,
+}
+
+impl Example {
+	/// Parses an example file, splitting off its header block if it has one.
+	pub fn parse(name: &str, text: &str) -> Result<Example, String> {
+		let mut example = Example {
+			name: name.to_string(),
+			input: text.to_string(),
+			part1: None,
+			part2: None,
+			params: Params::new(),
+		};
+
+		// without a header, the whole file is the input
+		let first = text.lines().next().unwrap_or("");
+		if ! first.split_once(':').is_some_and(|(key, _)| KEYS.contains(&key.trim())) {
+			return Ok(example);
+		}
+
+		let mut consumed = 0;
+		for line in text.split_inclusive('\n') {
+			consumed += line.len();
+			let line = line.trim();
+			if line == "---" {
+				example.input = text[consumed..].to_string();
+				return Ok(example);
+			}
+			if line.is_empty() {
+				continue;
+			}
+
+			let (key, value) = line.split_once(':')
+				.ok_or_else(|| format!("expected `key: value` in header, got {:?}", line))?;
+			let value = value.trim();
+			match key.trim() {
+				"part1" => example.part1 = Some(ExpectedAnswer(value.to_string())),
+				"part2" => example.part2 = Some(ExpectedAnswer(value.to_string())),
+				"params" => for pair in value.split(',').filter(|pair| ! pair.trim().is_empty()) {
+					let (name, value) = Params::parse_pair(pair)?;
+					example.params.set(&name, value);
+				},
+				key => return Err(format!("unknown header key {:?} (expected one of {:?})", key, KEYS)),
+			}
+		}
+		Err("header is missing its closing `---` line".to_string())
+	}
+
+	/// The expected answer for `part`, if given.
+	pub fn expected(&self, part: DayPart) -> Option<&ExpectedAnswer> {
+		match part {
+			DayPart::Part1 => self.part1.as_ref(),
+			DayPart::Part2 => self.part2.as_ref(),
+		}
+	}
+}
+
+/// An answer from an example's header, compared against the `Display` output of the part's answer.
+#[derive(Clone)]
+pub struct ExpectedAnswer(pub String);
+
+impl fmt::Display for ExpectedAnswer {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.0)
+	}
+}
+
+/// Written as-is, to match the answer it is compared against
+impl fmt::Debug for ExpectedAnswer {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.0)
+	}
+}
+
+impl<A: fmt::Display> PartialEq<A> for ExpectedAnswer {
+	fn eq(&self, answer: &A) -> bool {
+		answer.to_string().trim_end() == self.0.trim_end()
+	}
+}
+impl Eq for ExpectedAnswer {}
+
+/// Loads all examples for `day` from the `examples/` directory next to `input/`, sorted by name.
+pub fn load_examples(day: u8) -> Vec<Example> {
+	load_examples_from(DIR, day)
+}
+
+/// Loads all examples for `day` from `dir`, relative to the day crate, sorted by name.
+///
+/// Panics if the directory can't be read, or an example's header is invalid.
+pub fn load_examples_from<P: AsRef<Path>>(dir: P, day: u8) -> Vec<Example> {
+	let dir = match std::env::var_os("CARGO_MANIFEST_DIR") {
+		Some(manifest) => Path::new(&manifest).join(dir),
+		None => dir.as_ref().to_path_buf(),
+	};
+	let entries = fs::read_dir(&dir)
+		.unwrap_or_else(|e| panic!("unable to read examples from {}: {}", dir.display(), e));
+
+	let mut examples: Vec<Example> = entries
+		.map(|ent| ent.unwrap_or_else(|e| panic!("unable to read examples from {}: {}", dir.display(), e)).path())
+		.filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+		.filter_map(|path| {
+			let name = path.file_stem()?.to_str()?.to_string();
+			is_for_day(&name, day).then_some((name, path))
+		})
+		.map(|(name, path)| {
+			let text = fs::read_to_string(&path)
+				.unwrap_or_else(|e| panic!("unable to read example {}: {}", path.display(), e));
+			Example::parse(&name, &text)
+				.unwrap_or_else(|e| panic!("invalid example {}: {}", path.display(), e))
+		})
+		.collect();
+	examples.sort_by(|a, b| a.name.cmp(&b.name));
+	examples
+}
+
+/// Whether an example's file name (ie: `07-larger` or `07`) is for `day`
+fn is_for_day(name: &str, day: u8) -> bool {
+	let num = name.split_once('-').map_or(name, |(num, _)| num);
+	num.parse::<u8>() == Ok(day)
+}
+
+/// Tests `part` of `days` against every example for that day which has an answer for it.
+///
+/// Panics if the day has no examples at all. If none of them have an answer for `part`, the test passes after printing a warning.
+pub fn test_examples<Days: Variants, P: Part>(days: Days, part: P) {
+	let examples = load_examples(days.day());
+	if examples.is_empty() {
+		panic!("no examples found for day {} in {}", days.day(), DIR);
+	}
+	run_examples(days, part, &examples);
+}
+
+/// Tests `part` of `days` against a single example. Used by `#[aoch::examples]`, which emits a test per part with an answer.
+///
+/// Panics if the example has no answer for `part`.
+pub fn test_example<Days: Variants, P: Part>(days: Days, part: P, name: &str, text: &str) {
+	let example = Example::parse(name, text)
+		.unwrap_or_else(|e| panic!("invalid example {}: {}", name, e));
+	if example.expected(P::PART).is_none() {
+		panic!("example {} has no answer for {} in its header", name, P::PART);
+	}
+	run_examples(days, part, &[example]);
+}
+
+fn run_examples<Days: Variants, P: Part>(days: Days, part: P, examples: &[Example]) {
+	let cases: Vec<Case<&str, ExpectedAnswer>> = examples.iter()
+		.filter_map(|ex| {
+			let expected = ex.expected(P::PART)?.clone();
+			Some(Case::new(ex.input.as_str(), expected).named(&ex.name).with_params(ex.params.clone()))
+		})
+		.collect();
+
+	// shown past libtest's capturing, as the test still passes without having checked anything
+	match cases.is_empty() {
+		false => test_part(days, part, &cases),
+		true => notice(&format!("no examples for day {} have an answer for {} - skipping", days.day(), P::PART).yellow().to_string()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn header() {
+		let example = Example::parse("07-larger", "part1: 6\n\nparams: steps=10, size=7\n---\n1 2\n3 4\n").unwrap();
+		assert_eq!(example.input, "1 2\n3 4\n");
+		assert!(example.expected(DayPart::Part1).is_some_and(|ans| *ans == 6));
+		assert!(example.expected(DayPart::Part2).is_none());
+		assert_eq!(example.params, Params::new().with("steps", 10).with("size", 7));
+	}
+
+	#[test]
+	fn without_header() {
+		let example = Example::parse("07", "1: 2\n---\n").unwrap();
+		assert_eq!(example.input, "1: 2\n---\n");
+		assert!(example.part1.is_none() && example.part2.is_none());
+	}
+
+	#[test]
+	fn invalid_headers() {
+		assert!(Example::parse("07", "part1: 6\n1 2\n").is_err());
+		assert!(Example::parse("07", "part1: 6\npart3: 1\n---\n").is_err());
+	}
+
+	#[test]
+	fn named_by_day() {
+		assert!(is_for_day("07", 7));
+		assert!(is_for_day("07-larger", 7));
+		assert!(! is_for_day("17-larger", 7));
+		assert!(! is_for_day("notes", 7));
+	}
+
+	#[test]
+	fn only_examples_with_answers() {
+		#[derive(Debug, Clone, Copy)]
+		struct Sum;
+		impl crate::AoCDay for Sum {
+			type Data<'i> = Vec<u32>;
+			type Answer = u32;
+			fn day(&self) -> u8 {
+				7
+			}
+			fn parse(&self, input: &str) -> Vec<u32> {
+				input.split_whitespace().map(|n| n.parse().unwrap()).collect()
+			}
+			fn part1(&self, data: &mut Vec<u32>) -> u32 {
+				data.iter().sum()
+			}
+			fn part2(&self, _data: &mut Vec<u32>) -> u32 {
+				panic!("not run without an answer to check")
+			}
+		}
+
+		let examples = [
+			Example::parse("07", "part1: 10\n---\n1 2\n3 4\n").unwrap(),
+			Example::parse("07-bad", "part1: 5\n---\nx\n").unwrap(),
+		];
+		run_examples(Sum, crate::Part1, &examples[..1]);
+		run_examples(Sum, crate::Part2, &examples[..1]);
+		assert!(std::panic::catch_unwind(|| run_examples(Sum, crate::Part1, &examples)).is_err());
+	}
+
+}
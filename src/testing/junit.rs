@@ -0,0 +1,206 @@
+//! JUnit XML reports of test results, for dashboards and CI
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use super::{CaseResult, CaseRow};
+
+/// Set to `1` to write reports for all tests
+const JUNIT_VAR: &str = "AOCH_JUNIT";
+/// Where reports are written, within the target directory
+const REPORT_DIR: &str = "aoch-reports";
+
+/// The report files written by this test binary, so that suites run by several tests (ie: one per example file) don't overwrite
+/// each other's
+static WRITTEN: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+pub(super) fn enabled() -> bool {
+	std::env::var(JUNIT_VAR).is_ok_and(|v| v == "1")
+}
+
+/// The suite of a test that isn't tied to a day and part, named after the function it tests. (ie: `day07::tests::fuel` for a
+/// closure within that test)
+pub(super) fn suite_of<F>() -> String {
+	std::any::type_name::<F>()
+		.replace("::{{closure}}", "")
+}
+
+/// Writes the results of a test's cases to `target/aoch-reports/<crate>.<suite>.xml`
+pub(super) fn write_report(suite: &str, rows: &[CaseRow]) {
+	let dir = target_dir().join(REPORT_DIR);
+	let path = dir.join(format!("{}.xml", file_name(&report_name(suite))));
+	let written = fs::create_dir_all(&dir).and_then(|_| fs::write(&path, report(suite, rows)));
+	if let Err(e) = written {
+		eprintln!("unable to write JUnit report to {}: {}", path.display(), e);
+	}
+}
+
+/// `<crate>.<suite>`, followed by a count if this suite's report was already written
+fn report_name(suite: &str) -> String {
+	let name = match std::env::var("CARGO_PKG_NAME") {
+		Ok(krate) if ! krate.is_empty() && ! suite.starts_with(&krate) => format!("{}.{}", krate, suite),
+		_ => suite.to_string(),
+	};
+	let mut written = WRITTEN.lock().unwrap_or_else(|e| e.into_inner());
+	let unique = (1..)
+		.map(|n| match n {
+			1 => name.clone(),
+			n => format!("{}-{}", name, n),
+		})
+		.find(|name| ! written.contains(name))
+		.unwrap();
+	written.push(unique.clone());
+	unique
+}
+
+/// Formats the results of a test's cases as a JUnit XML report
+fn report(suite: &str, rows: &[CaseRow]) -> String {
+	let count = |result: fn(&CaseResult) -> bool| rows.iter().filter(|row| result(&row.result)).count();
+	let failures = count(|result| matches!(result, CaseResult::Failed));
+	let skipped = count(|result| matches!(result, CaseResult::Skipped));
+	let total: f64 = rows.iter().map(|row| row.elapsed.as_secs_f64()).sum();
+
+	let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n");
+	xml += &format!(
+		"\t<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.6}\">\n",
+		escape(suite), rows.len(), failures, skipped, total,
+	);
+	for row in rows {
+		xml += &format!(
+			"\t\t<testcase name=\"{}\" classname=\"{}\" time=\"{:.6}\"",
+			escape(&row.label), escape(suite), row.elapsed.as_secs_f64(),
+		);
+		match &row.failure {
+			None if matches!(row.result, CaseResult::Skipped) => {
+				xml += ">\n\t\t\t<skipped message=\"not solved yet\"/>\n\t\t</testcase>\n";
+			},
+			None => xml += "/>\n",
+			Some(failure) => {
+				let failure = strip_colors(failure);
+				let message = failure.lines().next().unwrap_or("");
+				xml += &format!(
+					">\n\t\t\t<failure message=\"{}\">{}</failure>\n\t\t</testcase>\n",
+					escape(message), escape(&failure),
+				);
+			},
+		}
+	}
+	xml += "\t</testsuite>\n</testsuites>\n";
+	xml
+}
+
+/// Cargo's target directory: `CARGO_TARGET_DIR` if set, else the one holding the running test binary
+fn target_dir() -> PathBuf {
+	if let Some(dir) = std::env::var_os("CARGO_TARGET_DIR") {
+		return PathBuf::from(dir);
+	}
+	// test binaries live in `target/<profile>/deps/`
+	let exe = std::env::current_exe().ok();
+	let from_exe = exe.as_ref()
+		.and_then(|exe| exe.ancestors().find(|dir| dir.file_name().is_some_and(|name| name == "target")))
+		.map(PathBuf::from);
+	from_exe.unwrap_or_else(|| {
+		let manifest = std::env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default();
+		PathBuf::from(manifest).join("target")
+	})
+}
+
+fn file_name(suite: &str) -> String {
+	suite.chars()
+		.map(|c| if c.is_ascii_alphanumeric() || "-_.".contains(c) { c } else { '_' })
+		.collect()
+}
+
+/// Escapes text for XML, writing characters XML can't hold at all (ie: most control characters) as `\u{1b}`
+fn escape(s: &str) -> String {
+	let mut out = String::with_capacity(s.len());
+	for c in s.chars() {
+		match c {
+			'&' => out += "&amp;",
+			'<' => out += "&lt;",
+			'>' => out += "&gt;",
+			'"' => out += "&quot;",
+			'\t' | '\n' | '\r' => out.push(c),
+			'\0'..='\u{1f}' | '\u{fffe}' | '\u{ffff}' => out += &format!("\\u{{{:x}}}", c as u32),
+			c => out.push(c),
+		}
+	}
+	out
+}
+
+/// Removes the terminal color codes added by `colored`
+pub(super) fn strip_colors(s: &str) -> String {
+	let mut out = String::with_capacity(s.len());
+	let mut chars = s.chars();
+	while let Some(c) = chars.next() {
+		if c == '\x1b' {
+			// skip to the end of `ESC [ ... m`
+			for c in chars.by_ref() {
+				if c == 'm' {
+					break;
+				}
+			}
+		} else {
+			out.push(c);
+		}
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use super::*;
+
+	fn row(label: &str, result: CaseResult, failure: Option<&str>) -> CaseRow {
+		CaseRow {
+			label: label.to_string(),
+			input: String::new(),
+			expected: String::new(),
+			actual: String::new(),
+			result,
+			elapsed: Duration::from_millis(2),
+			budget: None,
+			failure: failure.map(str::to_string),
+		}
+	}
+
+	#[test]
+	fn reports_each_case() {
+		let rows = [
+			row("#1", CaseResult::Passed, None),
+			row("#2", CaseResult::Failed, Some("\x1b[31mexpected 5\x1b[0m\ngot <4>")),
+			row("#3", CaseResult::Skipped, None),
+		];
+		let xml = report("day07-part1-Day07", &rows);
+		assert!(xml.contains("tests=\"3\" failures=\"1\" skipped=\"1\""));
+		assert!(xml.contains("<testcase name=\"#1\" classname=\"day07-part1-Day07\" time=\"0.002000\"/>"));
+		assert!(xml.contains("<failure message=\"expected 5\">expected 5\ngot &lt;4&gt;</failure>"));
+		assert!(xml.contains("<testcase name=\"#3\" classname=\"day07-part1-Day07\" time=\"0.002000\">\n\t\t\t<skipped"));
+	}
+
+	#[test]
+	fn escapes_invalid_characters() {
+		assert_eq!(escape("a\0b\u{7}c\td\n\"&"), "a\\u{0}b\\u{7}c\td\n&quot;&amp;");
+	}
+
+	#[test]
+	fn names_are_unique() {
+		let first = report_name("junit-test-suite");
+		assert!(first.ends_with("junit-test-suite"));
+		assert_eq!(report_name("junit-test-suite"), format!("{}-2", first));
+		assert_eq!(report_name("junit-test-suite"), format!("{}-3", first));
+	}
+
+	#[test]
+	fn suites_name_the_function() {
+		let closure = |n: &u32| *n;
+		assert_eq!(suite_of_val(&closure), "aoch::testing::junit::tests::suites_name_the_function");
+	}
+
+	fn suite_of_val<F>(_: &F) -> String {
+		suite_of::<F>()
+	}
+}
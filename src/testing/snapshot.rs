@@ -0,0 +1,171 @@
+//! Snapshot tests, for helper functions whose expected output is tedious to write by hand
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use colored::Colorize;
+
+use super::{check_cases, diff, Case, TestCase, TestConfig, TestInput};
+
+/// Set to `1` to re-record all snapshots, instead of checking against them
+const UPDATE_VAR: &str = "AOCH_UPDATE_SNAPSHOTS";
+/// Starts each case's entry in a snapshot file, followed by its key
+const HEADER: &str = "=== ";
+/// Starts each line of a recorded output, so that no output can be mistaken for a header
+const BODY: &str = "|";
+
+/// Tests `func` against outputs recorded in `snapshots/<name>.snap`, in the crate's directory.
+///
+/// Outputs are recorded per input, so inputs can be added, removed or reordered without affecting the others. Inputs without a
+/// recorded output are recorded on their first run, and all are re-recorded when `AOCH_UPDATE_SNAPSHOTS=1` is set.
+/// Outputs are compared by their `{:#?}` output, with strings compared as-is.
+///
+/// ```ignore
+/// run_snapshot("fuel_calc", |n| DayMe::calc_fuel(*n), &[12, 1969, 100756]);
+/// ```
+pub fn run_snapshot<I, O, F>(name: &str, func: F, inputs: &[I])
+where
+	I: TestInput,
+	O: fmt::Debug,
+	F: for<'a> Fn(&'a I) -> O,
+{
+	let path = snapshot_path(name);
+	let update = std::env::var(UPDATE_VAR).is_ok_and(|v| v == "1");
+	let mut recorded = match update {
+		true => HashMap::new(),
+		false => fs::read_to_string(&path).map(|file| read_entries(&file)).unwrap_or_default(),
+	};
+
+	// record any new inputs, before checking the rest
+	let keys: Vec<String> = inputs.iter().map(|input| key(&input.display())).collect();
+	let mut new = 0;
+	for (input, key) in inputs.iter().zip(&keys) {
+		if ! recorded.contains_key(key) {
+			recorded.insert(key.clone(), Snapshot(diff::render_pretty(&func(input))));
+			new += 1;
+		}
+	}
+	if new > 0 {
+		write_file(&path, &write_entries(inputs, &keys, &recorded));
+		eprintln!("{}", format!("recorded {} new snapshot(s) to {}", new, path.display()).yellow());
+	}
+
+	let cases: Vec<Case<&I, Snapshot>> = inputs.iter()
+		.zip(&keys)
+		.map(|(input, key)| Case::new(input, recorded[key].clone()))
+		.collect();
+	let checked = check_cases(&cases, &TestConfig::new(), &format!("snapshot-{}", name), |case| Snapshot(diff::render_pretty(&func(*case.input()))));
+	if let Err(failures) = checked {
+		panic!("{}\n\nset {}=1 to record the new outputs instead", failures, UPDATE_VAR);
+	}
+}
+
+/// A recorded output, compared as text
+#[derive(Clone, PartialEq, Eq)]
+struct Snapshot(String);
+
+/// Formatted as a string, so that failures show the output as-is
+impl fmt::Debug for Snapshot {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Debug::fmt(self.0.as_str(), f)
+	}
+}
+
+fn snapshot_path(name: &str) -> PathBuf {
+	let dir = std::env::var_os("CARGO_MANIFEST_DIR")
+		.map(PathBuf::from)
+		.unwrap_or_default();
+	dir.join("snapshots").join(format!("{}.snap", name))
+}
+
+/// Identifies an input's entry in a snapshot file, as a hash of the whole input.
+///
+/// FNV-1a, as the standard library's hasher may change between Rust versions, which would orphan every recorded snapshot.
+fn key(input: &str) -> String {
+	let hash = input.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3));
+	format!("{:016x}", hash)
+}
+
+/// Splits a snapshot file into each input's output, by key. Entries start with a `=== <key>: <input>` header line, and each line
+/// of the output follows it prefixed with `|`.
+fn read_entries(file: &str) -> HashMap<String, Snapshot> {
+	let mut entries: Vec<(&str, Vec<&str>)> = Vec::new();
+	for line in file.lines() {
+		if let Some(header) = line.strip_prefix(HEADER) {
+			let key = header.split(':').next().unwrap_or("");
+			entries.push((key, Vec::new()));
+		} else if let (Some(body), Some((_, entry))) = (line.strip_prefix(BODY), entries.last_mut()) {
+			entry.push(body.strip_prefix(' ').unwrap_or(body));
+		}
+	}
+	entries.into_iter()
+		.map(|(key, lines)| (key.to_string(), Snapshot(lines.join("\n"))))
+		.collect()
+}
+
+/// Formats a snapshot file of each input's recorded output, as read by [`read_entries`]
+fn write_entries<I: TestInput>(inputs: &[I], keys: &[String], snapshots: &HashMap<String, Snapshot>) -> String {
+	let mut file = String::new();
+	for (input, key) in inputs.iter().zip(keys) {
+		// only the input's first line, so the file stays readable. The key is what's matched.
+		let input = input.display();
+		let first = input.trim().lines().next().unwrap_or("");
+		file += &format!("{}{}: {}\n", HEADER, key, first);
+		for line in snapshots[key].0.split('\n') {
+			match line {
+				"" => file += &format!("{}\n", BODY),
+				line => file += &format!("{} {}\n", BODY, line),
+			}
+		}
+	}
+	file
+}
+
+fn write_file(path: &Path, file: &str) {
+	if let Some(dir) = path.parent() {
+		fs::create_dir_all(dir)
+			.unwrap_or_else(|e| panic!("unable to create snapshot directory {}: {}", dir.display(), e));
+	}
+	fs::write(path, file)
+		.unwrap_or_else(|e| panic!("unable to write snapshot file {}: {}", path.display(), e));
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn entries(inputs: &[&str], outputs: &[&str]) -> String {
+		let keys: Vec<String> = inputs.iter().map(|input| key(input)).collect();
+		let snapshots = keys.iter().cloned().zip(outputs.iter().map(|out| Snapshot(out.to_string()))).collect();
+		write_entries(inputs, &keys, &snapshots)
+	}
+
+	#[test]
+	fn entries_round_trip() {
+		let outputs = ["2", "first\n\n  indented\n=== #0: not a header\n|"];
+		let file = entries(&["12", "multi\nline"], &outputs);
+		let read = read_entries(&file);
+		assert_eq!(read.len(), 2);
+		assert_eq!(read[&key("12")], Snapshot("2".to_string()));
+		assert_eq!(read[&key("multi\nline")], Snapshot(outputs[1].to_string()));
+	}
+
+	#[test]
+	fn entries_are_matched_by_input() {
+		let file = entries(&["a", "b"], &["A", "B"]);
+		// as if the inputs had been reordered, with one inserted before them
+		let read = read_entries(&file);
+		assert_eq!(read.get(&key("new")), None);
+		assert_eq!(read[&key("b")], Snapshot("B".to_string()));
+		assert_eq!(read[&key("a")], Snapshot("A".to_string()));
+	}
+
+	#[test]
+	fn keys_are_stable() {
+		// FNV-1a test vectors, as a changed key would orphan every recorded snapshot
+		assert_eq!(key(""), "cbf29ce484222325");
+		assert_eq!(key("a"), "af63dc4c8601ec8c");
+	}
+}
@@ -5,13 +5,14 @@ use itertools::Itertools;
 use test_log::test;
 use aoch::AoCDay;
 #[cfg(test)] #[allow(unused_imports)]
-use aoch::{DayPart, run_test, test_runner, daystr};
+use aoch::{Part1, Part2, run_test, test_part, daystr};
 
 #[derive(Debug,Clone,Copy)]
 pub struct Day{{DayNum}};
 
 impl AoCDay for Day{{DayNum}} {
 	type Data<'i> = Vec<String>;
+	// use `aoch::Answers<A1, A2>` if the parts have different answer types
 	type Answer = usize;
 
 	fn day(&self) -> u8 { {{DayNum}} }
@@ -20,10 +21,10 @@ impl AoCDay for Day{{DayNum}} {
 		aoch::parsing::from_lines(input).unwrap()
 	}
 	fn part1(&self, _data: &mut Self::Data<'_>) -> Self::Answer {
-		todo!("Day {{DayNum}} Part 1")
+		aoch::unsolved()
 	}
 	fn part2(&self, _data: &mut Self::Data<'_>) -> Self::Answer {
-		todo!("Day {{DayNum}} Part 2")
+		aoch::unsolved()
 	}
 }
 
@@ -50,7 +51,7 @@ fn part1() {
 		(TEST_INPUT, 0),
 		(daystr!("{{DayNum}}"), 0),
 	];
-	test_runner::<Day{{DayNum}}, _>(Day{{DayNum}}, DayPart::Part1, &cases);
+	test_part(Day{{DayNum}}, Part1, &cases);
 }
 #[test]
 fn part2() {
@@ -58,5 +59,5 @@ fn part2() {
 		(TEST_INPUT, 0),
 		(daystr!("{{DayNum}}"), 0),
 	];
-	test_runner::<Day{{DayNum}}, _>(Day{{DayNum}}, DayPart::Part2, &cases);
+	test_part(Day{{DayNum}}, Part2, &cases);
 }
@@ -0,0 +1,128 @@
+//! `#[aoch::parse]`, `#[aoch::part1]` and `#[aoch::part2]`, which can only be used from outside the crate as their generated code
+//! refers to `::aoch`
+
+use aoch::{params, test_part, AoCDay, DayPart, DynDay, Part1, Part2, PartOutcome};
+
+mod day07 {
+	#[aoch::parse(day = 7)]
+	fn parse(input: &str) -> Vec<&str> {
+		input.lines().collect()
+	}
+
+	#[aoch::part1]
+	fn part1(lines: &[&str]) -> usize {
+		lines.len()
+	}
+
+	#[aoch::part2]
+	fn part2(lines: &[&str], part1: usize) -> String {
+		format!("{} {}", lines[0], part1)
+	}
+}
+use day07::Day07;
+
+mod day05 {
+	#[aoch::parse(day = 5)]
+	fn parse(input: &str, params: &aoch::Params) -> (Vec<u32>, u32) {
+		(input.lines().map(|l| l.parse().unwrap()).collect(), params.get("scale", 2))
+	}
+
+	#[aoch::part1]
+	fn part1(data: &(Vec<u32>, u32)) -> u32 {
+		data.0.iter().sum::<u32>() * data.1
+	}
+
+	#[aoch::part2]
+	fn part2(data: &mut (Vec<u32>, u32)) -> usize {
+		data.0.retain(|&n| n > 1);
+		data.0.len()
+	}
+}
+use day05::Day05;
+
+mod day25 {
+	#[aoch::parse(day = 25, part2 = false)]
+	fn parse(input: &str) -> u64 {
+		input.trim().parse().unwrap()
+	}
+
+	#[aoch::part1]
+	fn part1(n: &u64) -> u64 {
+		n * n
+	}
+}
+use day25::Day25;
+
+mod day06 {
+	pub mod fast {
+		#[aoch::parse(day = 6, name = Day06Fast, part2 = false)]
+		fn parse(input: &str) -> usize {
+			input.len()
+		}
+
+		#[aoch::part1]
+		fn part1(n: &usize) -> usize {
+			*n
+		}
+	}
+
+	pub mod slow {
+		#[aoch::parse(day = 6, name = Day06Slow, part1 = false)]
+		fn parse(input: &str) -> usize {
+			input.chars().count()
+		}
+
+		#[aoch::part2]
+		fn part2(n: &usize) -> usize {
+			*n * 2
+		}
+	}
+
+	pub use fast::Day06Fast;
+	pub use slow::Day06Slow;
+}
+
+#[test]
+fn borrowed_data() {
+	assert_eq!(AoCDay::day(&Day07), 7);
+	test_part(Day07, Part1, &[("a\nb\nc", 3)]);
+}
+
+#[test]
+fn part2_receives_part1() {
+	test_part(Day07, Part2, &[("a\nb\nc", "a 3".to_string())]);
+
+	// part 1 is run on part 2's behalf, but only if part 2 takes its answer
+	let report = Day07.run(Some(DayPart::Part2), "x\ny");
+	assert_eq!(report.parts[0].outcome, PartOutcome::Solved("x 2".to_string()));
+	assert!(report.parts[0].part1_elapsed.is_some());
+	assert_eq!(Day05.run(Some(DayPart::Part2), "1").parts[0].part1_elapsed, None);
+}
+
+#[test]
+fn params_and_mutable_data() {
+	assert_eq!(Day05.parse("1\n2").1, 2);
+	test_part(Day05, Part1, &[("1\n2", 6)]);
+	test_part(Day05, Part1, &[("1\n2", params!(scale = 3), 9)]);
+	test_part(Day05, Part2, &[("1\n2\n3", 2)]);
+}
+
+#[test]
+fn missing_parts() {
+	test_part(Day25, Part1, &[("4", 16)]);
+	let report = Day25.run(None, "4");
+	assert_eq!(report.part(DayPart::Part2).unwrap().outcome, PartOutcome::Unsolved);
+	// day 25's missing part 2 isn't shown
+	assert_eq!(report.to_string().lines().count(), 1, "{}", report);
+
+	let outcomes: Vec<PartOutcome> = day06::Day06Slow.run(None, "abc").parts.into_iter().map(|p| p.outcome).collect();
+	assert_eq!(outcomes, [PartOutcome::Unsolved, PartOutcome::Solved("6".to_string())]);
+}
+
+#[test]
+fn named_variants() {
+	assert_eq!(day06::Day06Fast.name(), "Day06Fast");
+	assert_eq!(AoCDay::day(&day06::Day06Slow), 6);
+	test_part(day06::Day06Fast, Part1, &[("abc", 3)]);
+	test_part(day06::Day06Slow, Part2, &[("abc", 6)]);
+}
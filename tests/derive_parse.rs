@@ -0,0 +1,119 @@
+//! `#[derive(aoch::Parse)]`, which can only be used from outside the crate as its generated code refers to `::aoch`
+
+use aoch::parsing::{from_grouped_lines, from_lines, FromLine};
+
+#[derive(aoch::Parse, Debug, PartialEq)]
+#[aoch(pattern = "move {count} from {from} to {to}")]
+struct Move {
+	count: usize,
+	from: usize,
+	to: usize,
+}
+
+#[derive(aoch::Parse, Debug, PartialEq)]
+#[aoch(pattern = "{min}-{max} {letter}: {password}")]
+struct Policy<'i> {
+	min: usize,
+	max: usize,
+	letter: char,
+	password: &'i str,
+}
+
+#[derive(aoch::Parse, Debug, PartialEq)]
+#[aoch(pattern = "<{0}|{1}>")]
+struct Pair<T>(T, T);
+
+#[derive(aoch::Parse, Debug, PartialEq)]
+#[aoch(pattern = "[{inner}] x{n}")]
+struct Nested<'i> {
+	inner: Policy<'i>,
+	n: u8,
+}
+
+#[derive(aoch::Parse, Debug, PartialEq)]
+enum Op {
+	#[aoch(pattern = "noop")]
+	Noop,
+	#[aoch(pattern = "add {}")]
+	Add(i64),
+	#[aoch(pattern = "addx {}")]
+	Addx(i64),
+	#[aoch(pattern = "jump {by} to {label}")]
+	Jump { by: i32, label: String },
+}
+
+#[derive(aoch::Parse, Debug, PartialEq)]
+enum Light {
+	#[aoch(pattern = "turn on {}")]
+	On(u32),
+	#[aoch(pattern = "turn on {} through {}")]
+	OnRange(u32, u32),
+	#[aoch(pattern = "toggle {}")]
+	Toggle(u32),
+}
+
+#[derive(aoch::Parse, Debug, PartialEq)]
+enum Wire<'i> {
+	#[aoch(pattern = "{} -> {}")]
+	Assign(&'i str, &'i str),
+	#[aoch(pattern = "{}")]
+	Value(u16),
+}
+
+#[test]
+fn structs() {
+	let moves: Vec<Move> = from_lines("move 1 from 2 to 3\nmove 10 from 1 to 9\n").unwrap();
+	assert_eq!(moves, [Move { count: 1, from: 2, to: 3 }, Move { count: 10, from: 1, to: 9 }]);
+
+	let input = String::from("1-3 a: abcde\n2-9 c: ccccccccc");
+	let policies: Vec<Policy> = from_lines(&input).unwrap();
+	assert_eq!(policies[1], Policy { min: 2, max: 9, letter: 'c', password: "ccccccccc" });
+
+	assert_eq!("<1|2>".parse::<Pair<u8>>().unwrap(), Pair(1, 2));
+	let nested = Nested::from_line("[1-3 a: xy] x4").unwrap();
+	assert_eq!((nested.inner.password, nested.n), ("xy", 4));
+}
+
+#[test]
+fn struct_errors() {
+	let err = from_lines::<Move>("move 1 from 2 to 3\nmove x from 1 to 9\n").unwrap_err();
+	assert_eq!((err.line, err.column), (Some(2), Some(6)));
+	assert!(err.to_string().contains("invalid usize \"x\""), "{}", err);
+
+	let err = from_grouped_lines::<Pair<i8>>("<1|2>\n\n<3|x>").unwrap_err();
+	assert_eq!((err.group, err.line, err.column), (Some(2), Some(3), Some(4)));
+
+	// located within the outer line, rather than the nested type's capture
+	let err = Nested::from_line("[1-z a: xy] x4").unwrap_err();
+	assert_eq!(err.column, Some(4));
+}
+
+#[test]
+fn enums() {
+	let ops: Vec<Op> = from_lines("noop\naddx -5\nadd 3\njump 2 to end").unwrap();
+	assert_eq!(ops, [Op::Noop, Op::Addx(-5), Op::Add(3), Op::Jump { by: 2, label: "end".to_string() }]);
+
+	let err = "mul 3".parse::<Op>().unwrap_err();
+	assert_eq!(err.error().to_string(), "expected one of \"noop\", \"add \", \"addx \", \"jump \"");
+	let err = from_lines::<Op>("noop\naddx five").unwrap_err();
+	assert_eq!((err.line, err.column), (Some(2), Some(6)));
+}
+
+#[test]
+fn shared_prefixes() {
+	let lights: Vec<Light> = from_lines("turn on 1 through 3\nturn on 4\ntoggle 2").unwrap();
+	assert_eq!(lights, [Light::OnRange(1, 3), Light::On(4), Light::Toggle(2)]);
+
+	// the variant that got furthest is reported
+	let err = "turn on 1 through x".parse::<Light>().unwrap_err();
+	assert_eq!(err.column, Some(19));
+	let err = "turn off 1".parse::<Light>().unwrap_err();
+	assert_eq!(err.error().to_string(), "expected one of \"turn on \", \"toggle \"");
+}
+
+#[test]
+fn leading_captures() {
+	let wires: Vec<Wire> = from_lines("123 -> x\n456\ny -> z").unwrap();
+	assert_eq!(wires, [Wire::Assign("123", "x"), Wire::Value(456), Wire::Assign("y", "z")]);
+	assert!(Wire::from_line("abc").is_err());
+}
@@ -0,0 +1,69 @@
+//! `aoch::scan!`, which can only be used from outside the crate as its generated code refers to `::aoch`
+//!
+//! Patterns that don't match their types (ie: the wrong number of captures) fail to compile, which is covered by the
+//! `compile_fail` examples in `src/parsing/scan.rs`.
+
+use aoch::parsing::{from_lines_with, try_from_lines_with, ParseError};
+
+fn policy(line: &str) -> Result<(usize, usize, char, &str), ParseError> {
+	aoch::scan!("{}-{} {}: {}", line => usize, usize, char, &str)
+}
+
+#[test]
+fn captures() {
+	assert_eq!(policy("1-3 a: abcde").unwrap(), (1, 3, 'a', "abcde"));
+
+	let line = String::from("move 3 from 1 to 2");
+	let (count, from, to) = aoch::scan!("move {} from {} to {}", &line => u32, u8, u8).unwrap();
+	assert_eq!((count, from, to), (3, 1, 2));
+
+	assert!(aoch::scan!("noop", "noop" =>).is_ok());
+	assert_eq!(aoch::scan!("{{{}}}", "{-4}" => i32).unwrap(), -4);
+}
+
+#[test]
+fn single_capture() {
+	// the value itself, rather than a 1-tuple
+	let x: i32 = aoch::scan!("x={}", "x=-4" => i32).unwrap();
+	assert_eq!(x, -4);
+	let name: &str = aoch::scan!("Hello, {}!", "Hello, world!" => &str).unwrap();
+	assert_eq!(name, "world");
+}
+
+#[test]
+fn borrows_from_the_input() {
+	let input = String::from("1-3 a: abcde\n\n2-9 c: ccccccccc\n");
+	let policies: Vec<(usize, usize, char, &str)> = from_lines_with(&input, |line| policy(line).unwrap());
+	assert_eq!(policies[1], (2, 9, 'c', "ccccccccc"));
+
+	// still borrowed once the closure has returned
+	let passwords: Vec<&str> = policies.iter().map(|p| p.3).collect();
+	assert_eq!(passwords, ["abcde", "ccccccccc"]);
+}
+
+#[test]
+fn errors() {
+	let err = policy("1-x a: abcde").unwrap_err();
+	assert_eq!(err.column, Some(3));
+	assert!(err.to_string().starts_with("column 3: invalid usize \"x\""), "{}", err);
+
+	let err = aoch::scan!("{}-{}", "1:3" => u8, u8).unwrap_err();
+	assert_eq!(err.error().to_string(), "expected \"-\" after this");
+	let err = aoch::scan!("#{}", "#7 " => u8).unwrap_err();
+	assert_eq!(err.column, Some(2));
+	let err = aoch::scan!("a{}", "b1" => u8).unwrap_err();
+	assert_eq!(err.error().to_string(), "expected \"a\"");
+
+	// located within the input by the line helpers
+	let err = try_from_lines_with("1-3 a: abcde\n\n1-3 b cdefg\n", policy).unwrap_err();
+	assert_eq!((err.line, err.column), (Some(3), Some(5)));
+}
+
+#[test]
+fn question_mark() {
+	fn day(line: &str) -> Result<u8, Box<dyn std::error::Error>> {
+		Ok(aoch::scan!("#{}", line => u8)?)
+	}
+	assert_eq!(day("#7").unwrap(), 7);
+	assert!(day("#x").unwrap_err().to_string().contains("invalid u8 \"x\""));
+}